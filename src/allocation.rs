@@ -0,0 +1,129 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+use crate::search::SubsetSumSolutions;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// 多个目标之间互不相交的分配结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    /// 每个目标分到的行（升序下标），无法满足的目标为 `None`
+    pub assignments: Vec<Option<Vec<usize>>>,
+    /// 没有分给任何目标的行
+    pub unallocated: Vec<usize>,
+}
+
+impl Allocation {
+    /// 满足了的目标个数
+    pub fn satisfied(&self) -> usize {
+        self.assignments.iter().filter(|a| a.is_some()).count()
+    }
+}
+
+/// 为多个目标同时分配互不相交的组合（多重子集和），每行最多用一次
+///
+/// `goals` 是各目标及其容差。按目标顺序逐个回溯：为当前目标依次尝试剩余行中的每个组合，
+/// 再为后面的目标分配；也允许当前目标不分配。返回满足目标数最多的方案，
+/// 个数相同时取回溯顺序中最早找到的那个；所有目标都满足时立即返回。
+/// 搜索预算用尽时返回目前最好的方案。
+pub fn allocate<T: HasAmount>(
+    items: &[T],
+    goals: &[(Amount, Amount)],
+    sizes: RangeInclusive<usize>,
+    control: &Arc<SearchControl>,
+) -> Allocation {
+    control.set_explored_unknown();
+    let mut allocator = Allocator {
+        amounts: items.iter().map(|n| n.amount()).collect(),
+        goals,
+        sizes,
+        control,
+        used: vec![false; items.len()],
+        current: vec![None; goals.len()],
+        satisfied: 0,
+        best: None,
+    };
+    allocator.visit(0);
+
+    let assignments = allocator.best.unwrap_or_else(|| vec![None; goals.len()]);
+    let mut used = vec![false; items.len()];
+    for &i in assignments.iter().flatten().flatten() {
+        used[i] = true;
+    }
+    Allocation {
+        assignments,
+        unallocated: (0..items.len()).filter(|&i| !used[i]).collect(),
+    }
+}
+
+struct Allocator<'a> {
+    amounts: Vec<Amount>,
+    goals: &'a [(Amount, Amount)],
+    sizes: RangeInclusive<usize>,
+    control: &'a Arc<SearchControl>,
+    /// 已分给前面目标的行
+    used: Vec<bool>,
+    current: Vec<Option<Vec<usize>>>,
+    satisfied: usize,
+    best: Option<Vec<Option<Vec<usize>>>>,
+}
+
+impl Allocator<'_> {
+    fn best_satisfied(&self) -> Option<usize> {
+        self.best
+            .as_ref()
+            .map(|best| best.iter().filter(|a| a.is_some()).count())
+    }
+
+    /// 为第 `k` 个及之后的目标分配，所有目标都已满足或需要停止时返回 `true`
+    fn visit(&mut self, k: usize) -> bool {
+        // 后面的目标全部满足也超不过已有方案时剪枝
+        let possible = self.satisfied + (self.goals.len() - k);
+        if self.best_satisfied().is_some_and(|best| possible <= best) {
+            return false;
+        }
+        if k == self.goals.len() {
+            self.best = Some(self.current.clone());
+            return self.satisfied == self.goals.len();
+        }
+
+        let available: Vec<usize> = (0..self.amounts.len()).filter(|&i| !self.used[i]).collect();
+        let values: Vec<Amount> = available.iter().map(|&i| self.amounts[i]).collect();
+        let (target, tolerance) = self.goals[k];
+        let solutions = SubsetSumSolutions::new(&values, target)
+            .tolerance(tolerance)
+            .items(self.sizes.clone())
+            .control(self.control.clone());
+        for solution in solutions {
+            let rows: Vec<usize> = solution.iter().map(|&j| available[j]).collect();
+            for &i in &rows {
+                self.used[i] = true;
+            }
+            self.current[k] = Some(rows);
+            self.satisfied += 1;
+            if self.visit(k + 1) {
+                return true;
+            }
+            self.satisfied -= 1;
+            if let Some(rows) = self.current[k].take() {
+                for i in rows {
+                    self.used[i] = false;
+                }
+            }
+        }
+        if self.control.stop_reason().is_some() {
+            // 停下时已分配的部分也可能比已有方案好
+            if self
+                .best_satisfied()
+                .is_none_or(|best| self.satisfied > best)
+            {
+                self.best = Some(self.current.clone());
+            }
+            return true;
+        }
+
+        // 当前目标不分配，继续后面的目标
+        self.visit(k + 1)
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// 允许的最大小数位数（保证 i64 仍有足够的整数位）
+pub const MAX_SCALE: u32 = 9;
+
+/// 单个金额以及一份输入中所有金额绝对值之和的上限（最小单位）
+///
+/// 目标、容差和任意组合之和都不超过它，相加相减的结果不会超出 i64 的范围。
+pub const MAX_TOTAL: i64 = i64::MAX / 4;
+
+/// 定点小数金额：以 10^-scale 为单位的整数表示，避免浮点误差
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount {
+    units: i64,
+    scale: u32,
+}
+
+/// 金额解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    /// 不是合法的数字
+    Invalid,
+    /// 小数位数超过了设定的精度
+    TooPrecise,
+    /// 数值超出范围
+    Overflow,
+}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAmountError::Invalid => write!(f, "invalid number"),
+            ParseAmountError::TooPrecise => write!(f, "too many decimal places"),
+            ParseAmountError::Overflow => write!(f, "number out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl Amount {
+    /// 由最小单位的整数构造
+    pub fn from_units(units: i64, scale: u32) -> Self {
+        Amount { units, scale }
+    }
+
+    /// 零值
+    pub fn zero(scale: u32) -> Self {
+        Amount { units: 0, scale }
+    }
+
+    /// 以最小单位表示的整数值
+    pub fn units(&self) -> i64 {
+        self.units
+    }
+
+    /// 小数位数
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn abs(&self) -> Self {
+        Amount {
+            units: self.units.abs(),
+            scale: self.scale,
+        }
+    }
+
+    /// 计算 `pct`% 的金额（向零取整），结果保持自身的精度，绝对值不超过 `MAX_TOTAL`
+    pub fn percent(&self, pct: Amount) -> Amount {
+        let divisor = 100 * 10i128.pow(pct.scale);
+        let units = i128::from(self.units) * i128::from(pct.units) / divisor;
+        let max = i128::from(MAX_TOTAL);
+        Amount {
+            units: units.clamp(-max, max) as i64,
+            scale: self.scale,
+        }
+    }
+
+    /// 按给定精度解析十进制字符串，例如 `-1.52`、`+3`、`.5`
+    ///
+    /// 多余的小数位只有全部为 0 时才会被接受，不做任何舍入；绝对值超过 `MAX_TOTAL` 时溢出。
+    pub fn parse(s: &str, scale: u32) -> Result<Self, ParseAmountError> {
+        let s = s.trim();
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (int_part, frac_part) = match digits.split_once('.') {
+            Some((i, f)) => (i, f),
+            None => (digits, ""),
+        };
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(ParseAmountError::Invalid);
+        }
+        if !int_part.bytes().all(|b| b.is_ascii_digit())
+            || !frac_part.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(ParseAmountError::Invalid);
+        }
+
+        let scale_len = scale as usize;
+        if frac_part.len() > scale_len && frac_part[scale_len..].bytes().any(|b| b != b'0') {
+            return Err(ParseAmountError::TooPrecise);
+        }
+
+        let mut units: i64 = 0;
+        let frac_digits = frac_part
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(scale_len);
+        for b in int_part.bytes().chain(frac_digits) {
+            units = units
+                .checked_mul(10)
+                .and_then(|u| u.checked_add(i64::from(b - b'0')))
+                .filter(|&u| u <= MAX_TOTAL)
+                .ok_or(ParseAmountError::Overflow)?;
+        }
+
+        Ok(Amount {
+            units: if negative { -units } else { units },
+            scale,
+        })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.units < 0 { "-" } else { "" };
+        let abs = self.units.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, abs);
+        }
+        let base = 10u64.pow(self.scale);
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            abs / base,
+            abs % base,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, rhs: Amount) -> Amount {
+        debug_assert_eq!(self.scale, rhs.scale);
+        Amount {
+            units: self.units + rhs.units,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, rhs: Amount) -> Amount {
+        debug_assert_eq!(self.scale, rhs.scale);
+        Amount {
+            units: self.units - rhs.units,
+            scale: self.scale,
+        }
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount {
+            units: -self.units,
+            scale: self.scale,
+        }
+    }
+}
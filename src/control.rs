@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 搜索提前结束的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// 超过了 `--timeout`
+    Timeout,
+    /// 超过了 `--max-nodes`
+    NodeLimit,
+    /// 用户按下了 Ctrl-C
+    Interrupted,
+}
+
+impl StopReason {
+    fn code(self) -> u8 {
+        match self {
+            StopReason::Timeout => 1,
+            StopReason::NodeLimit => 2,
+            StopReason::Interrupted => 3,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(StopReason::Timeout),
+            2 => Some(StopReason::NodeLimit),
+            3 => Some(StopReason::Interrupted),
+            _ => None,
+        }
+    }
+}
+
+/// 各个求解器共享的搜索控制：时间和节点预算、进度统计以及部分结果
+///
+/// 求解器每走一批节点调用一次 `tick`，返回 `true` 时应尽快停下；
+/// 同时通过 `add_explored`、`set_depth` 等更新进度，停下前用 `offer` 报告目前最接近目标的组合。
+#[derive(Debug)]
+pub struct SearchControl {
+    started: Instant,
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    nodes: AtomicU64,
+    reason: AtomicU8,
+    /// 当前搜索深度（已选元素个数）
+    depth: AtomicUsize,
+    /// 已找到的组合数
+    solutions: AtomicU64,
+    /// 已覆盖的搜索空间比例（f64 的位模式）
+    explored: AtomicU64,
+    /// 当前这一轮搜索占整个搜索空间的比例（f64 的位模式）
+    pass_weight: AtomicU64,
+    /// 覆盖比例能否估计
+    explored_known: AtomicBool,
+    /// 目前与目标距离最小的组合
+    best: Mutex<Option<(i64, Vec<usize>)>>,
+}
+
+impl Default for SearchControl {
+    fn default() -> Self {
+        SearchControl {
+            started: Instant::now(),
+            deadline: None,
+            max_nodes: None,
+            nodes: AtomicU64::new(0),
+            reason: AtomicU8::new(0),
+            depth: AtomicUsize::new(0),
+            solutions: AtomicU64::new(0),
+            explored: AtomicU64::new(0),
+            pass_weight: AtomicU64::new(1.0f64.to_bits()),
+            explored_known: AtomicBool::new(true),
+            best: Mutex::new(None),
+        }
+    }
+}
+
+impl SearchControl {
+    /// 不限时间和节点数
+    pub fn new() -> Self {
+        SearchControl::default()
+    }
+
+    /// 从现在起最多搜索 `timeout` 时长
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    /// 最多访问 `max_nodes` 个搜索节点
+    pub fn max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// 计入新访问的 `nodes` 个节点，预算用尽（或已被要求停止）时返回 `true`
+    pub fn tick(&self, nodes: u64) -> bool {
+        let total = self.nodes.fetch_add(nodes, Ordering::Relaxed) + nodes;
+        if self.stop_reason().is_some() {
+            return true;
+        }
+        if self.max_nodes.is_some_and(|max| total >= max) {
+            self.stop(StopReason::NodeLimit);
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.stop(StopReason::Timeout);
+        }
+        self.stop_reason().is_some()
+    }
+
+    /// 要求所有求解器停止，只保留第一次的原因
+    pub fn stop(&self, reason: StopReason) {
+        let _ =
+            self.reason
+                .compare_exchange(0, reason.code(), Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// 搜索是否被提前停止以及原因
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        StopReason::from_code(self.reason.load(Ordering::Relaxed))
+    }
+
+    /// 已访问的节点总数
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// 已覆盖的搜索空间比例，0 到 1 之间
+    pub fn explored(&self) -> f64 {
+        f64::from_bits(self.explored.load(Ordering::Relaxed)).min(1.0)
+    }
+
+    /// 目前最接近目标的组合及其与目标的距离
+    pub fn best(&self) -> Option<(i64, Vec<usize>)> {
+        self.best.lock().map(|best| best.clone()).unwrap_or(None)
+    }
+
+    /// 从创建起经过的时间
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// 当前搜索深度
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    pub fn set_depth(&self, depth: usize) {
+        self.depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// 已找到的组合数
+    pub fn solutions(&self) -> u64 {
+        self.solutions.load(Ordering::Relaxed)
+    }
+
+    pub fn add_solutions(&self, count: u64) {
+        self.solutions.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// 累加新覆盖的搜索空间比例（相对于当前这一轮）
+    pub fn add_explored(&self, delta: f64) {
+        if !self.explored_known() {
+            return;
+        }
+        let weight = f64::from_bits(self.pass_weight.load(Ordering::Relaxed));
+        let _ = self
+            .explored
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + delta * weight).to_bits())
+            });
+    }
+
+    /// 把搜索分成多轮进行时（例如迭代加深），设置接下来一轮占整个搜索空间的比例
+    pub fn pass_weight(&self, weight: f64) {
+        self.pass_weight.store(weight.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 覆盖比例能否估计
+    pub fn explored_known(&self) -> bool {
+        self.explored_known.load(Ordering::Relaxed)
+    }
+
+    /// 接下来的搜索由多次互相依赖的搜索组成（例如互不相交的分配、对账），覆盖比例无法估计，
+    /// 之后 `add_explored` 不再累加
+    pub fn set_explored_unknown(&self) {
+        self.explored_known.store(false, Ordering::Relaxed);
+    }
+
+    /// 开始一次新的搜索（例如下一个目标）：清除进度和最接近的组合，预算、节点数和停止原因保留
+    pub fn reset(&self) {
+        self.depth.store(0, Ordering::Relaxed);
+        self.explored.store(0.0f64.to_bits(), Ordering::Relaxed);
+        self.pass_weight.store(1.0f64.to_bits(), Ordering::Relaxed);
+        self.explored_known.store(true, Ordering::Relaxed);
+        if let Ok(mut best) = self.best.lock() {
+            *best = None;
+        }
+    }
+
+    /// 报告一个与目标距离为 `distance` 的组合，比目前最好的更接近时保留
+    pub fn offer(&self, distance: i64, indices: Vec<usize>) {
+        if let Ok(mut best) = self.best.lock() {
+            if best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                *best = Some((distance, indices));
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+
+/// 动态规划允许的最大和跨度（以最小单位计），表的大小与之成正比
+pub const MAX_DP_SPAN: u64 = 1 << 25;
+
+/// 尚未到达的和
+const UNREACHED: u32 = u32::MAX;
+/// 空集到达的和（即 0）
+const EMPTY: u32 = 0;
+
+/// 所有子集和可能的跨度：正数之和减去负数之和
+pub fn sum_span<T: HasAmount>(items: &[T]) -> u64 {
+    items
+        .iter()
+        .map(|n| n.amount().units().unsigned_abs())
+        .sum()
+}
+
+/// 以整数金额为下标的可达性表（伪多项式动态规划）
+///
+/// 用位图记录哪些和可达，按输入顺序逐个加入元素，每次整体移位求并。
+/// 同时记录每个和第一次由哪个元素到达，据此可以回溯出具体的组合。
+pub struct SubsetSumTable {
+    values: Vec<i64>,
+    /// 最小可能的和（所有负数之和），用作下标偏移
+    offset: i64,
+    /// `setter[s]` 为第一次到达和 `s + offset` 的元素下标加一，空集为 0
+    setter: Vec<u32>,
+    /// 由非空子集到达 0 时的最后一个元素，以及加入它之前的和
+    zero: Option<(usize, i64)>,
+    scale: u32,
+}
+
+impl SubsetSumTable {
+    /// 建表，和的跨度超过 `MAX_DP_SPAN` 时返回错误
+    ///
+    /// 搜索预算用尽时停止加入后续元素，得到的表只覆盖前面已处理的元素。
+    pub fn new<T: HasAmount>(
+        items: &[T],
+        scale: u32,
+        control: &SearchControl,
+    ) -> Result<Self, String> {
+        let span = sum_span(items);
+        if span > MAX_DP_SPAN {
+            return Err(format!(
+                "dynamic programming supports a sum span of at most {} units, got {}",
+                MAX_DP_SPAN, span
+            ));
+        }
+
+        let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+        let offset: i64 = values.iter().filter(|&&v| v < 0).sum();
+        let len = span as usize + 1;
+        let mut reached = vec![0u64; len.div_ceil(64)];
+        let mut setter = vec![UNREACHED; len];
+        let origin = (-offset) as usize;
+        reached[origin / 64] |= 1 << (origin % 64);
+        setter[origin] = EMPTY;
+
+        let mut zero = None;
+        for (i, &v) in values.iter().enumerate() {
+            // 以位图的字数计入节点数，以已加入的元素个数作为深度
+            if control.tick(reached.len() as u64) {
+                break;
+            }
+            control.set_depth(i + 1);
+            control.add_explored(1.0 / values.len() as f64);
+
+            let fresh = shift_or(&mut reached, v, len);
+            for s in fresh {
+                setter[s] = i as u32 + 1;
+            }
+            // 0 已被空集占用，单独记录第一个和为 0 的非空子集
+            if zero.is_none() {
+                let before = origin as i64 - v;
+                if before >= 0 && (before as usize) < len && setter[before as usize] <= i as u32 {
+                    zero = Some((i, before + offset));
+                }
+            }
+        }
+
+        Ok(SubsetSumTable {
+            values,
+            offset,
+            setter,
+            zero,
+            scale,
+        })
+    }
+
+    /// 和 `sum` 能否由非空子集到达
+    fn reachable(&self, sum: i64) -> bool {
+        if sum == 0 {
+            return self.zero.is_some();
+        }
+        let index = sum - self.offset;
+        index >= 0
+            && (index as usize) < self.setter.len()
+            && self.setter[index as usize] != UNREACHED
+    }
+
+    /// 回溯出和为 `sum` 的组合，返回升序下标
+    fn reconstruct(&self, sum: i64) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut sum = sum;
+        if sum == 0 {
+            let (last, before) = self.zero.expect("zero sum is not reachable");
+            indices.push(last);
+            sum = before;
+        }
+        loop {
+            let setter = self.setter[(sum - self.offset) as usize];
+            if setter == EMPTY {
+                break;
+            }
+            let i = setter as usize - 1;
+            indices.push(i);
+            sum -= self.values[i];
+        }
+        indices.sort_unstable();
+        indices
+    }
+
+    /// 在 `[target - tolerance, target + tolerance]` 内找一个组合，优先选离目标最近的和
+    ///
+    /// 只扫描表本身覆盖的和，代价不超过 O(跨度)；搜索预算用尽时返回 `None`。
+    pub fn find(
+        &self,
+        target: Amount,
+        tolerance: Amount,
+        control: &SearchControl,
+    ) -> Option<Vec<usize>> {
+        let target = target.units();
+        let tolerance = tolerance.units().abs();
+        self.nearest(target, control)
+            .filter(|&sum| (sum - target).abs() <= tolerance)
+            .map(|sum| self.reconstruct(sum))
+    }
+
+    /// 找和最接近 `target` 的组合，返回下标和差额；搜索预算用尽时返回 `None`
+    pub fn closest(&self, target: Amount, control: &SearchControl) -> Option<(Vec<usize>, Amount)> {
+        let target = target.units();
+        self.nearest(target, control).map(|sum| {
+            let diff = Amount::from_units(sum - target, self.scale);
+            (self.reconstruct(sum), diff)
+        })
+    }
+
+    /// 离 `target` 最近的可达和，距离相同时取较小的那个
+    ///
+    /// 从表的范围内离目标最近的位置向两侧扫描，目标在范围之外时只有一侧在表内，
+    /// 最多扫描整张表一次。
+    fn nearest(&self, target: i64, control: &SearchControl) -> Option<i64> {
+        let low = self.offset;
+        let high = self.offset + self.setter.len() as i64 - 1;
+        let start = target.clamp(low, high);
+        for d in 0..self.setter.len() as i64 {
+            if d % 1024 == 1023 && control.tick(1024) {
+                return None;
+            }
+            if let Some(sum) = [start - d, start + d]
+                .into_iter()
+                .filter(|sum| (low..=high).contains(sum))
+                .find(|&sum| self.reachable(sum))
+            {
+                return Some(sum);
+            }
+        }
+        None
+    }
+}
+
+/// 位图整体平移 `shift` 位后并入自身，返回新到达的位置
+fn shift_or(bits: &mut [u64], shift: i64, len: usize) -> Vec<usize> {
+    let words = shift.unsigned_abs() as usize / 64;
+    let rem = (shift.unsigned_abs() % 64) as u32;
+    let word_count = bits.len();
+    let mut fresh = Vec::new();
+    if shift == 0 {
+        return fresh;
+    }
+
+    // 左移时从高位往低位处理，右移时反过来，保证读到的都是本轮之前的状态
+    let order: Box<dyn Iterator<Item = usize>> = if shift > 0 {
+        Box::new((0..word_count).rev())
+    } else {
+        Box::new(0..word_count)
+    };
+    for w in order {
+        let shifted = if shift > 0 {
+            let high = w.checked_sub(words).map_or(0, |s| bits[s] << rem);
+            let low = match w.checked_sub(words + 1) {
+                Some(s) if rem > 0 => bits[s] >> (64 - rem),
+                _ => 0,
+            };
+            high | low
+        } else {
+            let low = bits.get(w + words).map_or(0, |&b| b >> rem);
+            let high = match bits.get(w + words + 1) {
+                Some(&b) if rem > 0 => b << (64 - rem),
+                _ => 0,
+            };
+            low | high
+        };
+
+        let mut new = shifted & !bits[w];
+        // 去掉超出范围的位
+        if w == word_count - 1 && !len.is_multiple_of(64) {
+            new &= (1u64 << (len % 64)) - 1;
+        }
+        bits[w] |= new;
+        while new != 0 {
+            fresh.push(w * 64 + new.trailing_zeros() as usize);
+            new &= new - 1;
+        }
+    }
+    fresh
+}
@@ -0,0 +1,466 @@
+use crate::amount::{Amount, ParseAmountError, MAX_TOTAL};
+use crate::locale::NumberFormat;
+use crate::record::Record;
+use calamine::{open_workbook_auto, Data, Reader};
+use csv::{ReaderBuilder, WriterBuilder};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 金额所在的列
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// 列号，从 0 开始
+    Index(usize),
+    /// 表头中的列名
+    Name(String),
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    /// 数字按从 1 开始的列号解析，其余的按列名
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.parse::<usize>() {
+            Ok(0) => Err("column numbers start at 1".to_string()),
+            Ok(n) => Ok(Column::Index(n - 1)),
+            Err(_) => Ok(Column::Name(s.to_string())),
+        }
+    }
+}
+
+/// 输入文件的格式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// 以空白分隔的文本，不处理引号
+    #[default]
+    Whitespace,
+    /// 以 `delimiter` 分隔的 CSV/TSV，`quote` 为 `None` 时不处理引号
+    Delimited { delimiter: u8, quote: Option<u8> },
+    /// Excel 或 OpenDocument 工作簿
+    Spreadsheet,
+}
+
+impl Format {
+    /// 按扩展名推断：`.csv` 用逗号，`.tsv`/`.tab` 用制表符，
+    /// `.xlsx`/`.xlsm`/`.xlsb`/`.xls`/`.ods` 为工作簿，其余按空白分隔
+    pub fn detect(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("csv") => Format::Delimited {
+                delimiter: b',',
+                quote: Some(b'"'),
+            },
+            Some("tsv") | Some("tab") => Format::Delimited {
+                delimiter: b'\t',
+                quote: Some(b'"'),
+            },
+            Some("xlsx" | "xlsm" | "xlsb" | "xls" | "ods") => Format::Spreadsheet,
+            _ => Format::Whitespace,
+        }
+    }
+}
+
+/// 文件开头的表头行
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Header {
+    /// 第一行在金额列不是数字时视为表头；按列名选择金额列时总有一行表头
+    #[default]
+    Auto,
+    /// 固定的表头行数，0 表示没有表头；列名取最后一行
+    Rows(usize),
+}
+
+/// 读取输入文件的选项
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputOptions {
+    /// 金额的小数位数
+    pub scale: u32,
+    pub format: Format,
+    pub header: Header,
+    /// 金额的书写格式
+    pub number: NumberFormat,
+    /// 金额所在的列，未指定时取表头为 `amount` 的列，没有则取第一列
+    pub column: Option<Column>,
+    /// 工作簿中的工作表，按名称或从 1 开始的序号，未指定时取第一个
+    pub sheet: Option<String>,
+    /// 标记列，其中的 `-` 是排除标记，不是金额的尾随负号
+    pub flag_column: Option<Column>,
+}
+
+/// 读入的数据表
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Table {
+    /// 文件的格式
+    pub format: Format,
+    /// 表头的各列，没有表头时为空
+    pub header: Vec<String>,
+    /// 金额所在的列，从 0 开始
+    pub column: usize,
+    /// 工作表的数据开始的列，从 0 开始；之前的空列不计入 `Record::fields`，文本文件为 0
+    pub first_column: usize,
+    /// 能解析出金额的数据行
+    pub records: Vec<Record>,
+    /// 无法读出金额的数据行
+    pub rejects: Vec<Reject>,
+}
+
+/// 无法读出金额的一行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reject {
+    /// 行号，从 1 开始
+    pub line: usize,
+    /// 原始行内容
+    pub text: String,
+    /// 无法读取的原因
+    pub reason: String,
+}
+
+impl Table {
+    /// 金额列的名称，没有表头时为 `amount`
+    pub fn amount_label(&self) -> &str {
+        match self.header.get(self.column).map(|h| h.trim()) {
+            Some(name) if !name.is_empty() => name,
+            _ => "amount",
+        }
+    }
+
+    /// 文件中的一列在 `Record::fields` 中的位置，金额列本身不在其中
+    pub fn field_index(&self, column: &Column) -> Result<usize, String> {
+        let index = match column {
+            Column::Index(index) => *index,
+            Column::Name(name) => named_column(&self.header, name, self.format)?,
+        };
+        if index < self.first_column {
+            return Err(format!("column {} is empty", index + 1));
+        }
+        match index.cmp(&self.column) {
+            Ordering::Less => Ok(index - self.first_column),
+            Ordering::Equal => Err(format!("column {} holds the amount", index + 1)),
+            Ordering::Greater => Ok(index - 1 - self.first_column),
+        }
+    }
+}
+
+/// 读取文件，工作簿按 `sheet_rows` 读出各行，文本文件见 `parse_records`
+pub fn read_records(path: &Path, options: &InputOptions) -> Result<Table, String> {
+    if options.format == Format::Spreadsheet {
+        let (rows, first_column) = sheet_rows(path, options.sheet.as_deref())?;
+        return build_table(rows, first_column, options);
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    // 表格软件导出的 CSV 常带 BOM
+    parse_records(content.trim_start_matches('\u{feff}'), options)
+}
+
+/// 解析文件内容：跳过表头，其余每行取选定列的金额，其他列保留为上下文
+///
+/// 空行被跳过，金额不合法或缺少该列的行记入 `rejects`；
+/// 小数位数超过精度时返回错误，不能静默丢弃。
+pub fn parse_records(content: &str, options: &InputOptions) -> Result<Table, String> {
+    let rows = match options.format {
+        Format::Whitespace => content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| Row {
+                line: index + 1,
+                text: line.to_string(),
+                cells: line.split_whitespace().map(str::to_string).collect(),
+                numeric: Vec::new(),
+            })
+            .collect(),
+        Format::Delimited { delimiter, quote } => delimited_rows(content, delimiter, quote)?,
+        Format::Spreadsheet => {
+            return Err(String::from("spreadsheets can only be read from a file"));
+        }
+    };
+    build_table(rows, 0, options)
+}
+
+/// 由拆分好的各行建表，见 `parse_records`；`first_column` 之前的列是工作表补齐的空列
+fn build_table(
+    rows: Vec<Row>,
+    first_column: usize,
+    options: &InputOptions,
+) -> Result<Table, String> {
+    let header_rows = match options.header {
+        Header::Rows(count) => count.min(rows.len()),
+        Header::Auto => usize::from(has_header(&rows, options)),
+    };
+    let mut rows = rows.into_iter();
+    let header = rows
+        .by_ref()
+        .take(header_rows)
+        .last()
+        .map(|row| row.cells)
+        .unwrap_or_default();
+    let column = match &options.column {
+        Some(Column::Index(index)) => *index,
+        Some(Column::Name(name)) => named_column(&header, name, options.format)?,
+        None => find_column(&header, "amount").unwrap_or(0),
+    };
+    // 找不到的标记列留给调用方报错
+    let flag_column = match &options.flag_column {
+        Some(Column::Index(index)) => Some(*index),
+        Some(Column::Name(name)) => named_column(&header, name, options.format).ok(),
+        None => None,
+    };
+
+    let mut records = Vec::new();
+    let mut rejects = Vec::new();
+    for mut row in rows {
+        if row.cells.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let reject = |row: Row, reason: String| Reject {
+            line: row.line,
+            text: row.text,
+            reason,
+        };
+        let cell = match row.cells.get(column) {
+            Some(cell) if cell.trim().is_empty() => {
+                rejects.push(reject(row, String::from("empty amount")));
+                continue;
+            }
+            Some(cell) => cell.trim().to_string(),
+            None => {
+                let reason = format!("no column {}", column + 1);
+                rejects.push(reject(row, reason));
+                continue;
+            }
+        };
+        // 空白分隔时，金额中的空格会把它拆到后面的列里，只读前半部分会得到错误的金额
+        let split = options.format == Format::Whitespace
+            && flag_column != Some(column + 1)
+            && row
+                .cells
+                .get(column + 1)
+                .is_some_and(|next| options.number.continues(&cell, next));
+        if split {
+            let reason = format!(
+                "amount `{}` continues in the next field `{}`, use --delimiter",
+                cell,
+                row.cells[column + 1]
+            );
+            rejects.push(reject(row, reason));
+            continue;
+        }
+        match row.amount(column, options) {
+            Ok(amount) => {
+                row.cells.remove(column);
+                row.cells.drain(..first_column.min(row.cells.len()));
+                records.push(Record {
+                    line: row.line,
+                    amount,
+                    text: row.text,
+                    fields: row.cells,
+                });
+            }
+            Err(ParseAmountError::TooPrecise) => {
+                return Err(format!(
+                    "line {}: `{}` has more than {} decimal places, increase --scale",
+                    row.line, cell, options.scale
+                ))
+            }
+            Err(ParseAmountError::Overflow) => {
+                rejects.push(reject(row, format!("amount `{}` is out of range", cell)));
+            }
+            Err(ParseAmountError::Invalid) => {
+                rejects.push(reject(row, format!("invalid amount `{}`", cell)));
+            }
+        }
+    }
+
+    // 所有金额绝对值之和有上限，任何组合求和都不会溢出
+    let total = records
+        .iter()
+        .try_fold(0i64, |total, r| total.checked_add(r.amount.units().abs()));
+    if total.is_none_or(|total| total > MAX_TOTAL) {
+        return Err(format!(
+            "the amounts add up to more than {} in absolute value, lower --scale",
+            Amount::from_units(MAX_TOTAL, options.scale)
+        ));
+    }
+
+    Ok(Table {
+        format: options.format,
+        header,
+        column,
+        first_column,
+        records,
+        rejects,
+    })
+}
+
+/// 文件中的一行，拆分成各列
+struct Row {
+    line: usize,
+    text: String,
+    cells: Vec<String>,
+    /// 工作表中哪些单元格是数值，文本文件为空
+    numeric: Vec<bool>,
+}
+
+impl Row {
+    /// 解析第 `column` 列的金额；工作表中的数值单元格已是规范的十进制数，不按语言区域解析
+    fn amount(&self, column: usize, options: &InputOptions) -> Result<Amount, ParseAmountError> {
+        let cell = self.cells[column].trim();
+        if self.numeric.get(column).copied().unwrap_or(false) {
+            Amount::parse(cell, options.scale)
+        } else {
+            options.number.parse(cell, options.scale)
+        }
+    }
+}
+
+/// 用 csv 读取分隔文本；每行的原始内容按记录的起始位置从文件中截取，带引号的多行字段保持原样
+fn delimited_rows(content: &str, delimiter: u8, quote: Option<u8>) -> Result<Vec<Row>, String> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .quoting(quote.is_some())
+        .quote(quote.unwrap_or(b'"'))
+        .from_reader(content.as_bytes());
+
+    let mut rows = Vec::new();
+    let mut starts = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let position = record.position().expect("csv records carry their position");
+        starts.push(position.byte() as usize);
+        rows.push(Row {
+            line: position.line() as usize,
+            text: String::new(),
+            cells: record.iter().map(str::to_string).collect(),
+            numeric: Vec::new(),
+        });
+    }
+    starts.push(content.len());
+    for (row, range) in rows.iter_mut().zip(starts.windows(2)) {
+        row.text = content[range[0]..range[1]]
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+    }
+    Ok(rows)
+}
+
+/// 读取工作簿中的一个工作表；行号和列号与工作表中的一致，数据之前的列用空单元格补齐，
+/// 同时返回数据开始的列。每行的原始内容为各单元格以逗号分隔的文本
+fn sheet_rows(path: &Path, sheet: Option<&str>) -> Result<(Vec<Row>, usize), String> {
+    let mut workbook = open_workbook_auto(path).map_err(|e| e.to_string())?;
+    let names = workbook.sheet_names();
+    let name = match sheet {
+        None => names.first(),
+        Some(sheet) => names.iter().find(|n| n.as_str() == sheet).or_else(|| {
+            sheet
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| names.get(n.checked_sub(1)?))
+        }),
+    };
+    let name = match name {
+        Some(name) => name.clone(),
+        None => {
+            return Err(format!(
+                "sheet `{}` not found, the workbook has {}",
+                sheet.unwrap_or("1"),
+                names.join(", ")
+            ))
+        }
+    };
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e| format!("sheet `{}`: {}", name, e))?;
+
+    let (first_row, first_column) = range.start().unwrap_or((0, 0));
+    let mut rows = Vec::new();
+    for (index, cells) in range.rows().enumerate() {
+        let numeric = std::iter::repeat_n(false, first_column as usize)
+            .chain(
+                cells
+                    .iter()
+                    .map(|c| matches!(c, Data::Int(_) | Data::Float(_))),
+            )
+            .collect();
+        let cells: Vec<String> = std::iter::repeat_n(String::new(), first_column as usize)
+            .chain(cells.iter().map(cell_text))
+            .collect();
+        let mut text = WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+        text.write_record(cells.iter().skip(first_column as usize))
+            .map_err(|e| e.to_string())?;
+        let text = text.into_inner().map_err(|e| e.to_string())?;
+        rows.push(Row {
+            line: first_row as usize + index + 1,
+            text: String::from_utf8_lossy(&text).trim_end().to_string(),
+            cells,
+            numeric,
+        });
+    }
+    Ok((rows, first_column as usize))
+}
+
+/// 单元格的文本；浮点数按 Excel 的 15 位有效数字取整，避免 `0.1 + 0.2` 这样的二进制误差
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Float(value) => format!("{:.14e}", value)
+            .parse::<f64>()
+            .unwrap_or(*value)
+            .to_string(),
+        _ => cell.to_string(),
+    }
+}
+
+/// 自动判断第一行是否为表头：按第一行中的列名选择金额列、第一行含 `amount` 列，
+/// 或者金额列的值不是数字时视为表头
+fn has_header(rows: &[Row], options: &InputOptions) -> bool {
+    let (row, first) = match rows.first() {
+        Some(row) => (row, &row.cells),
+        None => return false,
+    };
+    let column = match &options.column {
+        Some(Column::Name(name)) if find_column(first, name).is_some() => return true,
+        Some(Column::Name(name)) => match column_letters(name) {
+            Some(index) if options.format == Format::Spreadsheet => index,
+            _ => return true,
+        },
+        Some(Column::Index(index)) => *index,
+        None if find_column(first, "amount").is_some() => return true,
+        None => 0,
+    };
+    column >= first.len() || row.amount(column, options) == Err(ParseAmountError::Invalid)
+}
+
+/// 按列名查找列；工作表的表头中没有时把 `A`、`AB` 这样的名称当作列字母
+fn named_column(header: &[String], name: &str, format: Format) -> Result<usize, String> {
+    find_column(header, name)
+        .or_else(|| column_letters(name).filter(|_| format == Format::Spreadsheet))
+        .ok_or_else(|| format!("column `{}` not found in the header", name))
+}
+
+/// 工作表的列字母（`A` 到 `ZZZ`，不区分大小写）对应的列号，从 0 开始
+fn column_letters(name: &str) -> Option<usize> {
+    if name.is_empty() || name.len() > 3 || !name.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    let number = name.bytes().fold(0, |n, b| {
+        n * 26 + usize::from(b.to_ascii_uppercase() - b'A') + 1
+    });
+    Some(number - 1)
+}
+
+/// 在表头中查找列名，先精确匹配，再忽略大小写
+fn find_column(header: &[String], name: &str) -> Option<usize> {
+    header.iter().position(|h| h.trim() == name).or_else(|| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    })
+}
@@ -0,0 +1,14 @@
+pub mod allocation;
+pub mod amount;
+pub mod control;
+pub mod dp;
+pub mod input;
+pub mod locale;
+pub mod mitm;
+pub mod netting;
+pub mod parallel;
+pub mod progress;
+pub mod reconcile;
+pub mod record;
+pub mod search;
+pub mod selection;
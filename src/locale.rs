@@ -0,0 +1,226 @@
+use crate::amount::{Amount, ParseAmountError};
+
+/// 常见的货币符号，出现在金额前后时忽略
+const CURRENCY_SYMBOLS: &[char] = &[
+    '$', '¥', '€', '£', '₹', '₩', '₽', '¢', '₺', '₫', '฿', '₱', '₪', '元', '円',
+];
+
+/// 可以出现在金额前后的 ISO 4217 货币代码（另加常见的 `RMB`）
+const CURRENCY_CODES: &[&str] = &[
+    "AED", "AUD", "BRL", "CAD", "CHF", "CNY", "CZK", "DKK", "EUR", "GBP", "HKD", "HUF", "IDR",
+    "ILS", "INR", "JPY", "KRW", "MXN", "MYR", "NOK", "NZD", "PHP", "PLN", "RMB", "RUB", "SAR",
+    "SEK", "SGD", "THB", "TRY", "TWD", "USD", "VND", "ZAR",
+];
+
+/// 可以出现在 `$` 之前的国家或地区前缀，例如 `US$`、`HK$`
+const DOLLAR_PREFIXES: &[&str] = &["A", "AU", "C", "CA", "HK", "NT", "NZ", "S", "SG", "US"];
+
+/// 金额的书写格式：小数点和千位分隔符
+///
+/// 解析时还接受全角数字和符号、前后的货币符号或常见的货币代码，
+/// 以及会计写法的负数 `(500.00)` 和 `500.00-`。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    decimal_sep: char,
+    /// 为空格时同时接受不换行空格
+    thousands_sep: Option<char>,
+}
+
+impl Default for NumberFormat {
+    /// `1,234.56`
+    fn default() -> Self {
+        NumberFormat {
+            decimal_sep: '.',
+            thousands_sep: Some(','),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// 按语言区域选择格式，例如 `en-US`、`de_DE`、`fr`、`de-CH`
+    pub fn for_locale(locale: &str) -> Result<Self, String> {
+        let locale = locale.replace('_', "-").to_ascii_lowercase();
+        let (language, region) = locale.split_once('-').unwrap_or((&locale, ""));
+        let (decimal_sep, thousands_sep) = match (language, region) {
+            (_, "ch") | (_, "li") => ('.', '\''),
+            ("en" | "zh" | "ja" | "ko" | "th" | "he" | "hi" | "ms" | "fil", _) => ('.', ','),
+            ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "vi", _) => {
+                (',', '.')
+            }
+            ("fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "hu", _) => {
+                (',', ' ')
+            }
+            _ => return Err(format!("unsupported locale `{}`", locale)),
+        };
+        Ok(NumberFormat {
+            decimal_sep,
+            thousands_sep: Some(thousands_sep),
+        })
+    }
+
+    /// 改用 `decimal_sep` 作为小数点；与千位分隔符相同时两者互换
+    pub fn decimal_sep(mut self, decimal_sep: char) -> Self {
+        if self.thousands_sep == Some(decimal_sep) {
+            self.thousands_sep = Some(self.decimal_sep);
+        }
+        self.decimal_sep = decimal_sep;
+        self
+    }
+
+    /// 改用 `thousands_sep` 作为千位分隔符，`None` 表示不允许分隔符
+    pub fn thousands_sep(mut self, thousands_sep: Option<char>) -> Self {
+        self.thousands_sep = thousands_sep;
+        self
+    }
+
+    /// 小数点和千位分隔符不能相同，也不能是数字或正负号
+    pub fn validate(&self) -> Result<(), String> {
+        let invalid = |c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '(' | ')');
+        if invalid(self.decimal_sep) || self.thousands_sep.is_some_and(invalid) {
+            return Err(String::from(
+                "separators cannot be digits, signs or parentheses",
+            ));
+        }
+        if self.thousands_sep == Some(self.decimal_sep) {
+            return Err(format!(
+                "the decimal and thousands separators are both `{}`",
+                self.decimal_sep
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按此格式解析金额，规范化之后交给 `Amount::parse`
+    pub fn parse(&self, s: &str, scale: u32) -> Result<Amount, ParseAmountError> {
+        let s: String = s.chars().map(half_width).collect();
+        let mut s = strip_currency(&s);
+
+        // 会计写法的负数
+        let mut negative = false;
+        if let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            negative = true;
+            s = strip_currency(inner);
+        } else if let Some(rest) = s.strip_suffix('-') {
+            negative = true;
+            s = strip_currency(rest);
+        }
+        if negative && s.starts_with(['-', '+']) {
+            return Err(ParseAmountError::Invalid);
+        }
+
+        let (sign, digits) = match s.strip_prefix(['-', '+']) {
+            Some(rest) => (&s[..1], strip_currency(rest)),
+            None => ("", s),
+        };
+        let (int_part, frac_part) = match digits.split_once(self.decimal_sep) {
+            Some((i, f)) => (i, Some(f)),
+            None => (digits, None),
+        };
+        let int_part = self.ungroup(int_part)?;
+
+        let mut normalized = String::new();
+        if negative {
+            normalized.push('-');
+        }
+        normalized.push_str(sign);
+        normalized.push_str(&int_part);
+        if let Some(frac_part) = frac_part {
+            normalized.push('.');
+            normalized.push_str(frac_part);
+        }
+        Amount::parse(&normalized, scale)
+    }
+
+    /// 按空白拆分的文本中，紧跟在金额 `cell` 之后的 `next` 是否本属于同一个金额：
+    /// 会计写法的尾随负号（`500.00 -`），或以空格为千位分隔符时被拆开的数字组（`1 234,56`）
+    pub fn continues(&self, cell: &str, next: &str) -> bool {
+        let cell: String = cell.chars().map(half_width).collect();
+        let next: String = next.chars().map(half_width).collect();
+        if next == "-" {
+            return true;
+        }
+        if self.thousands_sep != Some(' ')
+            || cell.contains(self.decimal_sep)
+            || !cell.ends_with(|c: char| c.is_ascii_digit())
+        {
+            return false;
+        }
+        let digits = next.len() - next.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &next[digits..];
+        digits == 3
+            && (rest.starts_with(self.decimal_sep) || matches!(strip_currency(rest), "" | "-"))
+    }
+
+    /// 去掉整数部分的千位分隔符：第一组 1 到 3 位，最后一组 3 位，
+    /// 中间各组 2 或 3 位（兼容印度的两位分组）
+    fn ungroup(&self, int_part: &str) -> Result<String, ParseAmountError> {
+        let is_sep = |c: char| match self.thousands_sep {
+            Some(' ') => matches!(c, ' ' | '\u{a0}' | '\u{202f}'),
+            Some(sep) => c == sep,
+            None => false,
+        };
+        if !int_part.contains(is_sep) {
+            return Ok(int_part.to_string());
+        }
+        let groups: Vec<&str> = int_part.split(is_sep).collect();
+        let last = groups.len() - 1;
+        let valid = groups.iter().enumerate().all(|(i, group)| {
+            let len = group.chars().count();
+            match i {
+                0 => (1..=3).contains(&len),
+                _ if i == last => len == 3,
+                _ => (2..=3).contains(&len),
+            }
+        });
+        if valid {
+            Ok(groups.concat())
+        } else {
+            Err(ParseAmountError::Invalid)
+        }
+    }
+}
+
+/// 全角字符转成对应的半角字符，Unicode 减号转成 `-`
+fn half_width(c: char) -> char {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        '\u{ffe5}' => '¥',
+        '\u{ffe1}' => '£',
+        '\u{3000}' => ' ',
+        '\u{2212}' => '-',
+        _ => c,
+    }
+}
+
+/// 去掉首尾的空白、货币符号和货币代码（如 `USD`、`HK$`）
+fn strip_currency(s: &str) -> &str {
+    let mut s = s.trim();
+    loop {
+        let stripped = s
+            .trim_start_matches(CURRENCY_SYMBOLS)
+            .trim_end_matches(CURRENCY_SYMBOLS)
+            .trim();
+        let stripped = strip_code(stripped);
+        if stripped.len() == s.len() {
+            return s;
+        }
+        s = stripped;
+    }
+}
+
+/// 去掉开头或结尾的货币代码，以及 `US$` 这样的 `$` 前缀；
+/// 其他字母不去掉，`INV100` 这样的编号不会被当成金额
+fn strip_code(s: &str) -> &str {
+    let prefix = s.len() - s.trim_start_matches(|c: char| c.is_ascii_uppercase()).len();
+    let (letters, rest) = s.split_at(prefix);
+    if CURRENCY_CODES.contains(&letters)
+        || DOLLAR_PREFIXES.contains(&letters) && rest.starts_with('$')
+    {
+        return rest.trim_start();
+    }
+    let rest = s.trim_end_matches(|c: char| c.is_ascii_uppercase());
+    if CURRENCY_CODES.contains(&&s[rest.len()..]) {
+        return rest.trim_end();
+    }
+    s
+}
@@ -1,100 +1,515 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use csv::WriterBuilder;
-use std::fs::{canonicalize, File};
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
-use std::str::FromStr;
-use std::time::Instant;
+use find::allocation::allocate;
+use find::amount::{Amount, ParseAmountError, MAX_SCALE};
+use find::control::{SearchControl, StopReason};
+use find::dp::{sum_span, SubsetSumTable, MAX_DP_SPAN};
+use find::input::{read_records, Column, Format, Header, InputOptions, Table};
+use find::locale::NumberFormat;
+use find::mitm::{closest_in_the_middle, meet_in_the_middle, MAX_MITM_ITEMS};
+use find::netting::{net, Netting};
+use find::parallel::{fewest_first, find_solutions};
+use find::progress::{ProgressFormat, ProgressReporter};
+use find::reconcile::{reconcile, MatchKind, Reconciliation};
+use find::record::Record;
+use find::search::{find_closest_combination, SubsetSumSolutions};
+use find::selection::RowSelection;
+use std::cmp::Reverse;
+use std::fs::{self, canonicalize, File};
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 搜索因时间或节点预算用尽而未完成时的退出码
+const EXIT_INCOMPLETE: i32 = 3;
+
+/// 搜索被 Ctrl-C 中断时的退出码（128 + SIGINT）
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// 参数、输入文件或求解出错时的退出码
+const EXIT_ERROR: i32 = 1;
+
+/// 进度输出的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 摘要中最多列出的无法读取的行数
+const MAX_REPORTED_REJECTS: usize = 10;
+
+/// 查找组合所用的算法
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// Pick one based on the number of amounts and their sum span
+    Auto,
+    /// Depth-first backtracking with sum bounds
+    Backtrack,
+    /// Meet-in-the-middle, for up to ~44 amounts
+    Mitm,
+    /// Dynamic programming over integer sums, for many amounts with a bounded range
+    Dp,
+}
+
+/// 有多个组合满足条件时优先报告哪些
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Prefer {
+    /// Combinations of rows that come first in the file
+    Earliest,
+    /// Combinations with the fewest amounts (iterative deepening)
+    Fewest,
+    /// Combinations made of the largest amounts
+    Largest,
+}
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input file path
-    #[arg(short, long, value_parser, value_name = "FILE_PATH", required_unless_present = "file")]
+    #[arg(
+        short,
+        long,
+        value_parser,
+        value_name = "FILE_PATH",
+        required_unless_present = "file_pos"
+    )]
     file: Option<String>,
 
-    /// Target number
-    #[arg(short, long, value_parser, value_name = "TARGET", required_unless_present = "target", allow_negative_numbers = true)]
-    target: Option<f64>,
+    /// Target number (repeat for several targets)
+    #[arg(
+        short,
+        long,
+        value_parser,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_pos", "targets_file"],
+        allow_negative_numbers = true
+    )]
+    target: Vec<String>,
+
+    /// File with one target per line, each solved against the same amounts
+    #[arg(long, value_name = "FILE_PATH")]
+    targets_file: Option<String>,
+
+    /// Number of decimal places used for exact amount matching
+    #[arg(short, long, value_name = "SCALE", default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=MAX_SCALE as i64))]
+    scale: u32,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Find every combination that sums up to the target, not just the first
+    #[arg(short, long)]
+    all: bool,
+
+    /// Maximum number of combinations to find in --all mode
+    #[arg(short, long, value_name = "N", requires = "all")]
+    limit: Option<usize>,
+
+    /// Accept combinations whose sum is within this amount of the target
+    #[arg(long, value_name = "AMOUNT")]
+    tolerance: Option<String>,
+
+    /// Accept combinations whose sum is within this percentage of the target
+    #[arg(long, value_name = "PERCENT")]
+    tolerance_pct: Option<String>,
+
+    /// Only accept combinations of at least this many amounts
+    #[arg(long, value_name = "N")]
+    min_items: Option<usize>,
+
+    /// Only accept combinations of at most this many amounts
+    #[arg(long, value_name = "N")]
+    max_items: Option<usize>,
+
+    /// Rows that must be part of the combination, by line number or ID (comma separated)
+    #[arg(long, value_name = "ROWS", value_delimiter = ',')]
+    include_rows: Vec<String>,
+
+    /// Rows to leave out of the search, by line number or ID (comma separated)
+    #[arg(long, value_name = "ROWS", value_delimiter = ',')]
+    exclude_rows: Vec<String>,
+
+    /// Column, by header name or number, whose value `include`/`+` pins a row and `exclude`/`-` leaves it out
+    #[arg(long, value_name = "NAME|N")]
+    flag_column: Option<Column>,
+
+    /// Assign disjoint combinations to the targets, using each row at most once
+    #[arg(long)]
+    disjoint: bool,
+
+    /// Which combinations to report first when several match [default: earliest];
+    /// giving it also makes the automatic choice use backtracking
+    #[arg(long, value_enum)]
+    prefer: Option<Prefer>,
+
+    /// When nothing matches, report the combination closest to the target
+    #[arg(long)]
+    closest: bool,
+
+    /// Search algorithm
+    #[arg(long, value_enum, default_value_t = Algorithm::Auto)]
+    algorithm: Algorithm,
+
+    /// Number of threads for the backtracking search (0 uses all cores)
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    threads: usize,
+
+    /// Stop searching after this many seconds and report partial results
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+
+    /// Stop searching after visiting this many search nodes
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<u64>,
+
+    /// Do not report search progress on stderr
+    #[arg(short, long, conflicts_with = "progress")]
+    quiet: bool,
+
+    /// Format of the periodic progress reports on stderr
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ProgressFormat::Text)]
+    progress: ProgressFormat,
 
     /// Input file path (as positional argument)
-    #[arg(value_name = "FILE_PATH", last(true))]
+    #[arg(value_name = "FILE_PATH", conflicts_with = "file")]
     file_pos: Option<String>,
 
     /// Target number (as positional argument)
-    #[arg(value_name = "TARGET", last(true), allow_negative_numbers = true)]
-    target_pos: Option<f64>,
+    #[arg(
+        value_name = "TARGET",
+        conflicts_with = "target",
+        allow_negative_numbers = true
+    )]
+    target_pos: Option<String>,
 }
 
-/// 从文件中读取数据并转换为 f64 类型的向量
-fn read_numbers_from_file(file_path: &str) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Match two files against each other by groups of rows with equal sums
+    Reconcile(ReconcileArgs),
+    /// Find disjoint groups of entries that net to zero within one file
+    Netting(NettingArgs),
+}
 
-    let mut numbers = Vec::new();
-    let mut skip_header = true;
+#[derive(clap::Args, Debug)]
+struct ReconcileArgs {
+    /// First input file, e.g. the bank statement (side A)
+    #[arg(value_name = "FILE_A")]
+    left: String,
 
-    for line in reader.lines() {
-        let line = line?;
-        if skip_header {
-            skip_header = false;
-            continue;
-        }
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if let Some(number_str) = parts.get(0) {
-            if let Ok(number) = f64::from_str(number_str) {
-                numbers.push(number);
+    /// Second input file, e.g. the general ledger (side B)
+    #[arg(value_name = "FILE_B")]
+    right: String,
+
+    /// Number of decimal places used for exact amount matching
+    #[arg(short, long, value_name = "SCALE", default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=MAX_SCALE as i64))]
+    scale: u32,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Accept groups whose sums differ by at most this amount
+    #[arg(long, value_name = "AMOUNT")]
+    tolerance: Option<String>,
+
+    /// Maximum number of rows on each side of a group
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    max_group: usize,
+
+    /// Stop searching after this many seconds and report the rest as unmatched
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+
+    /// Stop searching after visiting this many search nodes
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct NettingArgs {
+    /// Input file path
+    #[arg(value_name = "FILE_PATH")]
+    file: String,
+
+    /// Number of decimal places used for exact amount matching
+    #[arg(short, long, value_name = "SCALE", default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=MAX_SCALE as i64))]
+    scale: u32,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Accept groups whose sum is within this amount of zero
+    #[arg(long, value_name = "AMOUNT")]
+    tolerance: Option<String>,
+
+    /// Maximum number of entries in a group
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    max_group: usize,
+
+    /// Stop searching after this many seconds and report the rest as residue
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+
+    /// Stop searching after visiting this many search nodes
+    #[arg(long, value_name = "N")]
+    max_nodes: Option<u64>,
+}
+
+/// 输入文件的解析选项，主命令和各子命令共用
+#[derive(clap::Args, Debug)]
+struct InputArgs {
+    /// Column holding the amounts, by header name or number [default: `amount` if present, else 1]
+    #[arg(long, value_name = "NAME|N")]
+    column: Option<Column>,
+
+    /// Field delimiter, e.g. `,`, `;` or `tab` [default: by file extension, whitespace for .txt]
+    #[arg(long, value_name = "CHAR", value_parser = parse_char)]
+    delimiter: Option<u8>,
+
+    /// Quote character in delimited files
+    #[arg(long, value_name = "CHAR", value_parser = parse_char, default_value = "\"")]
+    quote: u8,
+
+    /// Do not treat any character as a quote in delimited files
+    #[arg(long)]
+    no_quoting: bool,
+
+    /// The file has no header row [default: the first row is a header if its amount is not a number]
+    #[arg(long, conflicts_with = "header_rows")]
+    no_header: bool,
+
+    /// Number of header rows to skip; column names are taken from the last one
+    #[arg(long, value_name = "N")]
+    header_rows: Option<usize>,
+
+    /// Locale whose number format the amounts use, e.g. `en-US`, `de-DE`, `fr-FR` or `de-CH`
+    #[arg(long, value_name = "LOCALE", value_parser = NumberFormat::for_locale)]
+    locale: Option<NumberFormat>,
+
+    /// Decimal separator of the amounts [default: `.`]
+    #[arg(long, value_name = "CHAR")]
+    decimal_sep: Option<char>,
+
+    /// Thousands separator of the amounts, `space` or `none` [default: `,`]
+    #[arg(long, value_name = "CHAR")]
+    thousands_sep: Option<String>,
+
+    /// Stop if any line of the input cannot be read, instead of skipping it
+    #[arg(long)]
+    strict: bool,
+
+    /// Worksheet of an .xlsx/.xls/.ods workbook, by name or number [default: the first]
+    #[arg(long, value_name = "NAME|N")]
+    sheet: Option<String>,
+}
+
+impl InputArgs {
+    /// 给定文件的读取选项：工作簿按扩展名识别，文本文件指定了分隔符时按分隔文本读取，否则按扩展名推断
+    fn options(&self, file_path: &str, scale: u32) -> Result<InputOptions, String> {
+        let quote = (!self.no_quoting).then_some(self.quote);
+        let format = match (self.delimiter, Format::detect(Path::new(file_path))) {
+            (Some(_), Format::Spreadsheet) => {
+                return Err(String::from("--delimiter cannot be used with spreadsheets"));
+            }
+            (None, Format::Spreadsheet) => Format::Spreadsheet,
+            (_, _) if self.sheet.is_some() => {
+                return Err(String::from("--sheet only applies to spreadsheets"));
             }
+            (Some(delimiter), _) => Format::Delimited { delimiter, quote },
+            (None, Format::Delimited { delimiter, .. }) => Format::Delimited { delimiter, quote },
+            (None, Format::Whitespace) => Format::Whitespace,
+        };
+        let header = match (self.no_header, self.header_rows) {
+            (true, _) => Header::Rows(0),
+            (false, Some(rows)) => Header::Rows(rows),
+            (false, None) => Header::Auto,
+        };
+        let mut number = self.locale.unwrap_or_default();
+        if let Some(decimal_sep) = self.decimal_sep {
+            number = number.decimal_sep(decimal_sep);
         }
+        if let Some(thousands_sep) = &self.thousands_sep {
+            number = number.thousands_sep(match thousands_sep.as_str() {
+                "none" | "" => None,
+                "space" => Some(' '),
+                sep => match sep.parse::<char>() {
+                    Ok(c) => Some(c),
+                    Err(_) => return Err(format!("Invalid --thousands-sep `{}`", sep)),
+                },
+            });
+        }
+        number.validate()?;
+        Ok(InputOptions {
+            scale,
+            format,
+            header,
+            number,
+            column: self.column.clone(),
+            sheet: self.sheet.clone(),
+            flag_column: None,
+        })
     }
-
-    Ok(numbers)
 }
 
-/// 使用回溯算法查找第一个可能的组合
-fn find_first_combination(nums: &[f64], target: f64) -> Option<Vec<f64>> {
-    let mut result = None;
-    let mut path = Vec::new();
+/// 解析单个 ASCII 字符，`tab` 或 `\t` 表示制表符
+fn parse_char(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!("`{}` is not a single ASCII character", value)),
+    }
+}
 
-    backtrack_first(nums, target, 0, &mut path, &mut result);
-    result
+/// 从文件中读取数据并按给定精度转换为定点金额，保留行号、原始内容和其余各列
+fn read_numbers_from_file(
+    file_path: &str,
+    scale: u32,
+    input: &InputArgs,
+    flag_column: Option<&Column>,
+) -> Result<Table, String> {
+    let options = InputOptions {
+        flag_column: flag_column.cloned(),
+        ..input.options(file_path, scale)?
+    };
+    read_records(Path::new(file_path), &options)
 }
 
-/// 回溯函数
-fn backtrack_first(
-    nums: &[f64],
-    target: f64,
-    start: usize,
-    path: &mut Vec<f64>,
-    result: &mut Option<Vec<f64>>,
-) {
-    if let Some(ref mut _res) = result {
-        // 已经找到了一个解，直接返回
-        return;
+/// 报告各文件中无法读出金额的行，并写入 `output_file`；`strict` 时有这样的行就返回错误
+///
+/// 没有这样的行时删除之前运行留下的 `output_file`，免得它被当成这次的结果。
+fn report_rejects(
+    files: &[(&str, &Table)],
+    strict: bool,
+    output_file: &Path,
+) -> Result<(), String> {
+    let total: usize = files.iter().map(|(_, table)| table.rejects.len()).sum();
+    if total == 0 {
+        match fs::remove_file(output_file) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                eprintln!("Failed to remove stale {}: {}", output_file.display(), e)
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+    for (file, table) in files.iter().filter(|(_, table)| !table.rejects.is_empty()) {
+        eprintln!(
+            "Skipped {} line(s) of {} without a readable amount:",
+            table.rejects.len(),
+            file
+        );
+        for reject in table.rejects.iter().take(MAX_REPORTED_REJECTS) {
+            eprintln!("  line {}: {}", reject.line, reject.reason);
+        }
+        if table.rejects.len() > MAX_REPORTED_REJECTS {
+            eprintln!(
+                "  ... and {} more",
+                table.rejects.len() - MAX_REPORTED_REJECTS
+            );
+        }
     }
+    match write_rejects_to_csv(files, output_file) {
+        Ok(_) => eprintln!("Rejected lines written to {}", output_file.display()),
+        Err(e) => eprintln!("Failed to write rejected lines to CSV: {}", e),
+    }
+    if strict {
+        return Err(format!(
+            "{} line(s) could not be read, stopping because of --strict",
+            total
+        ));
+    }
+    Ok(())
+}
 
-    let sum: f64 = path.iter().sum();
-    if sum == target {
-        *result = Some(path.clone());
-        return;
-    } else if sum > target.abs() {
-        // 如果超过了目标值的绝对值，则直接返回
-        return;
+/// 把无法读出金额的行写入 CSV：文件、行号、原因和原始内容
+fn write_rejects_to_csv(
+    files: &[(&str, &Table)],
+    output_file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(b',')
+        .from_path(output_file)?;
+    wtr.write_record(["file", "line", "reason", "row"])?;
+    for (file, table) in files {
+        for reject in &table.rejects {
+            wtr.write_record([
+                file,
+                reject.line.to_string().as_str(),
+                &reject.reason,
+                &reject.text,
+            ])?;
+        }
     }
 
-    for i in start..nums.len() {
-        path.push(nums[i]);
-        backtrack_first(nums, target, i + 1, path, result);
-        path.pop(); // 回溯
-        if result.is_some() {
-            break;
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 从目标文件中读取目标值，每行一个（取第一列），跳过空行、`#` 开头的注释和表头
+fn read_targets_from_file(file_path: &str, scale: u32) -> Result<Vec<Amount>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+    let mut targets = Vec::new();
+    let mut first = true;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+        let value = match line.split_whitespace().next() {
+            Some(value) if !value.starts_with('#') => value,
+            _ => continue,
+        };
+        let is_first = std::mem::replace(&mut first, false);
+        match Amount::parse(value, scale) {
+            Ok(target) => targets.push(target),
+            // 注释之后的第一行不是数字时视为表头
+            Err(ParseAmountError::Invalid) if is_first => {}
+            Err(e) => {
+                return Err(format!(
+                    "{} line {}: invalid target `{}`: {}",
+                    file_path,
+                    index + 1,
+                    value,
+                    e
+                ))
+            }
         }
     }
+    Ok(targets)
+}
+
+/// 收集 `-t`、位置参数和 `--targets-file` 给出的所有目标值
+fn parse_targets(args: &Args) -> Result<Vec<Amount>, String> {
+    let mut targets = Vec::new();
+    for value in args.target.iter().chain(&args.target_pos) {
+        let target = Amount::parse(value, args.scale)
+            .map_err(|e| format!("Invalid target `{}`: {}", value, e))?;
+        targets.push(target);
+    }
+    if let Some(path) = &args.targets_file {
+        targets.extend(read_targets_from_file(path, args.scale)?);
+    }
+    if targets.is_empty() {
+        return Err(String::from("No target given"));
+    }
+    Ok(targets)
 }
 
-/// 将组合写入 CSV 文件，每个组合作为一列
+/// 将组合写入 CSV 文件，每个组合占 行号/金额/原始行 三列，末尾附上合计与差额；
+/// `closest` 时组合只是最接近目标的那个，金额列的表头标为 `closest`
 fn write_combinations_to_csv(
-    combinations: &[Vec<f64>],
+    combinations: &[Vec<Record>],
+    target: Amount,
+    amount_label: &str,
+    closest: bool,
     output_file: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut wtr = WriterBuilder::new()
@@ -102,77 +517,1063 @@ fn write_combinations_to_csv(
         .delimiter(b',') // 使用逗号作为分隔符
         .from_path(output_file)?;
 
-    if let Some(combination) = combinations.first() {
-        // 获取组合的最大长度，用于确定需要写多少行
-        let max_length = combination.len();
-
-        for i in 0..max_length {
-            let mut record = Vec::new();
-            for comb in combinations {
-                if i < comb.len() {
-                    record.push(comb[i].to_string());
-                } else {
-                    record.push(String::from("")); // 填充空格
+    let amount_label = if closest {
+        format!("{} (closest)", amount_label)
+    } else {
+        amount_label.to_string()
+    };
+    let header: Vec<&str> = combinations
+        .iter()
+        .flat_map(|_| ["line", amount_label.as_str(), "row"])
+        .collect();
+    wtr.write_record(&header)?;
+
+    // 获取组合的最大长度，用于确定需要写多少行
+    let max_length = combinations.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    for i in 0..max_length {
+        let mut record = Vec::new();
+        for comb in combinations {
+            match comb.get(i) {
+                Some(row) => {
+                    record.push(row.line.to_string());
+                    record.push(row.amount.to_string());
+                    record.push(row.text.clone());
                 }
+                None => record.extend([String::new(), String::new(), String::new()]), // 填充空格
             }
-            wtr.write_record(&record)?;
         }
+        wtr.write_record(&record)?;
     }
 
+    let sums: Vec<Amount> = combinations
+        .iter()
+        .map(|c| sum_of(c, target.scale()))
+        .collect();
+    let sum_row: Vec<String> = sums
+        .iter()
+        .flat_map(|sum| [String::from("sum"), sum.to_string(), String::new()])
+        .collect();
+    let diff_row: Vec<String> = sums
+        .iter()
+        .flat_map(|&sum| {
+            [
+                String::from("diff"),
+                (sum - target).to_string(),
+                String::new(),
+            ]
+        })
+        .collect();
+    wtr.write_record(&sum_row)?;
+    wtr.write_record(&diff_row)?;
+
     wtr.flush()?;
     Ok(())
 }
 
-fn main() {
-    let args = Args::parse();
+/// 多个目标时的结果文件：每个目标占 行号/金额/原始行 三列，金额列的表头为目标值，
+/// 末尾附上合计与差额；不满足条件、退而给出的最接近的组合在表头中标为 `closest`。
+/// 给出 `unallocated` 时最后再加三列未分配的行
+fn write_targets_to_csv(
+    results: &[TargetResult],
+    unallocated: Option<&[Record]>,
+    scale: u32,
+    output_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(b',')
+        .from_path(output_file)?;
+
+    // 每个目标只写第一个组合，没有组合的目标留空
+    let mut columns: Vec<(String, &[Record], Option<Amount>)> = results
+        .iter()
+        .map(|result| {
+            let label = if result.closest {
+                format!("{} (closest)", result.target)
+            } else {
+                result.target.to_string()
+            };
+            let rows = result
+                .combinations
+                .first()
+                .map_or(&[][..], |c| c.as_slice());
+            (label, rows, Some(result.target))
+        })
+        .collect();
+    if let Some(unallocated) = unallocated {
+        columns.push((String::from("unallocated"), unallocated, None));
+    }
+    let header: Vec<&str> = columns
+        .iter()
+        .flat_map(|(label, _, _)| ["line", label.as_str(), "row"])
+        .collect();
+    wtr.write_record(&header)?;
 
-    let file_path = args
-        .file
-        .unwrap_or_else(|| args.file_pos.expect("--file or FILE_PATH is required"));
-    let target = args
-        .target
-        .unwrap_or_else(|| args.target_pos.expect("--target or TARGET is required"));
+    let max_length = columns.iter().map(|(_, c, _)| c.len()).max().unwrap_or(0);
+    for i in 0..max_length {
+        let mut record = Vec::new();
+        for (_, rows, _) in &columns {
+            match rows.get(i) {
+                Some(row) => {
+                    record.push(row.line.to_string());
+                    record.push(row.amount.to_string());
+                    record.push(row.text.clone());
+                }
+                None => record.extend([String::new(), String::new(), String::new()]),
+            }
+        }
+        wtr.write_record(&record)?;
+    }
+
+    // 没有组合的目标不写合计与差额，未分配的行没有差额
+    let mut sum_row = Vec::new();
+    let mut diff_row = Vec::new();
+    for (_, rows, target) in &columns {
+        let sum = sum_of(rows, scale);
+        match target {
+            Some(_) if rows.is_empty() => {
+                sum_row.extend([String::new(), String::new(), String::new()]);
+                diff_row.extend([String::new(), String::new(), String::new()]);
+            }
+            Some(target) => {
+                sum_row.extend([String::from("sum"), sum.to_string(), String::new()]);
+                diff_row.extend([
+                    String::from("diff"),
+                    (sum - *target).to_string(),
+                    String::new(),
+                ]);
+            }
+            None => {
+                sum_row.extend([String::from("sum"), sum.to_string(), String::new()]);
+                diff_row.extend([String::new(), String::new(), String::new()]);
+            }
+        }
+    }
+    wtr.write_record(&sum_row)?;
+    wtr.write_record(&diff_row)?;
 
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 组合中所有金额之和
+fn sum_of(combination: &[Record], scale: u32) -> Amount {
+    combination
+        .iter()
+        .fold(Amount::zero(scale), |acc, r| acc + r.amount)
+}
+
+/// 解析容差参数，同时给出金额和百分比时取较宽的那个
+fn parse_tolerance(args: &Args, target: Amount) -> Result<Amount, String> {
+    let mut tolerance = parse_tolerance_amount(args.tolerance.as_deref(), args.scale)?;
+    if let Some(value) = &args.tolerance_pct {
+        let pct = Amount::parse(value, 4)
+            .map_err(|e| format!("Invalid tolerance percentage `{}`: {}", value, e))?;
+        if pct < Amount::zero(4) {
+            return Err(String::from("Tolerance must not be negative"));
+        }
+        tolerance = tolerance.max(target.abs().percent(pct));
+    }
+    Ok(tolerance)
+}
+
+/// 解析 `--tolerance` 给出的金额，未给出时为 0，不能为负
+fn parse_tolerance_amount(value: Option<&str>, scale: u32) -> Result<Amount, String> {
+    let tolerance = match value {
+        Some(value) => Amount::parse(value, scale)
+            .map_err(|e| format!("Invalid tolerance `{}`: {}", value, e))?,
+        None => Amount::zero(scale),
+    };
+    if tolerance < Amount::zero(scale) {
+        return Err(String::from("Tolerance must not be negative"));
+    }
+    Ok(tolerance)
+}
+
+/// 解析组合元素个数的范围，未指定时不限
+fn parse_sizes(args: &Args) -> Result<RangeInclusive<usize>, String> {
+    let min = args.min_items.unwrap_or(1);
+    let max = args.max_items.unwrap_or(usize::MAX);
+    if max == 0 || min > max {
+        return Err(format!(
+            "Invalid item count range: --min-items {} --max-items {}",
+            min, max
+        ));
+    }
+    Ok(min..=max)
+}
+
+/// 根据输入规模、金额跨度和元素个数限制自动选择算法
+fn choose_algorithm(records: &[Record], all: bool, sized: bool, ordered: bool) -> Algorithm {
+    // 条目不多时回溯已经足够快，而且按输入顺序给出第一个组合；
+    // 限制了元素个数时回溯可以据此剪枝；明确指定了 --prefer 时只有回溯能保证顺序
+    if records.len() <= 24 || sized || ordered {
+        return Algorithm::Backtrack;
+    }
+    // 动态规划每个和只保留一个组合，无法列出全部组合
+    if !all && sum_span(records) <= MAX_DP_SPAN {
+        return Algorithm::Dp;
+    }
+    if records.len() <= MAX_MITM_ITEMS {
+        return Algorithm::Mitm;
+    }
+    Algorithm::Backtrack
+}
+
+/// 为动态规划建表；表与目标值无关，所有目标共用一张
+fn dp_table(
+    records: &[Record],
+    args: &Args,
+    control: &SearchControl,
+) -> Result<SubsetSumTable, String> {
+    if args.all {
+        return Err(String::from(
+            "--algorithm dp finds a single combination and cannot be used with --all",
+        ));
+    }
+    if args.min_items.is_some() || args.max_items.is_some() {
+        return Err(String::from(
+            "--algorithm dp cannot be used with --min-items or --max-items",
+        ));
+    }
+    SubsetSumTable::new(records, args.scale, control)
+}
+
+/// 选定的算法；动态规划的表与目标值无关，建好后所有目标共用
+enum Solver {
+    Backtrack,
+    Mitm,
+    Dp(SubsetSumTable),
+}
+
+/// 按选定的算法查找组合，返回各组合的下标
+fn search(
+    records: &[Record],
+    target: Amount,
+    tolerance: Amount,
+    sizes: RangeInclusive<usize>,
+    solver: &Solver,
+    args: &Args,
+    control: &Arc<SearchControl>,
+) -> Result<Vec<Vec<usize>>, String> {
+    let limit = if args.all {
+        args.limit.unwrap_or(usize::MAX)
+    } else {
+        1
+    };
+    let found = match solver {
+        Solver::Dp(table) => table.find(target, tolerance, control).into_iter().collect(),
+        Solver::Backtrack => {
+            let solutions = SubsetSumSolutions::new(records, target)
+                .tolerance(tolerance)
+                .items(sizes)
+                .control(control.clone());
+            match args.prefer {
+                Some(Prefer::Fewest) => fewest_first(solutions, limit, args.threads)?,
+                // 明确指定了顺序时，多线程也要给出回溯顺序中的第一个组合
+                prefer => find_solutions(solutions, limit, args.threads, prefer.is_some())?,
+            }
+        }
+        Solver::Mitm => meet_in_the_middle(records, target, tolerance, sizes, limit, control)?,
+    };
+    Ok(found)
+}
+
+/// 按下标取出组合中的记录，连同固定选中的行一起按在文件中的行号排列
+fn rows_of(records: &[Record], pinned: &[Record], indices: &[usize]) -> Vec<Record> {
+    let mut rows: Vec<Record> = pinned
+        .iter()
+        .cloned()
+        .chain(indices.iter().map(|&i| records[i].clone()))
+        .collect();
+    rows.sort_by_key(|r| r.line);
+    rows
+}
+
+/// 组合之和与目标值的距离
+fn distance(records: &[Record], indices: &[usize], target: Amount) -> i64 {
+    let sum: i64 = indices.iter().map(|&i| records[i].amount.units()).sum();
+    (sum - target.units()).abs()
+}
+
+/// 把组合格式化为 `[a (line 2), b (line 5)]` 的形式
+fn format_combination(combination: &[Record]) -> String {
+    let items: Vec<String> = combination
+        .iter()
+        .map(|r| format!("{} (line {})", r.amount, r.line))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// 一个目标的结果：要写入结果文件的组合，以及它们是否只是最接近目标的组合
+struct TargetResult {
+    target: Amount,
+    combinations: Vec<Vec<Record>>,
+    closest: bool,
+}
+
+/// 为一个目标查找组合并输出到终端，返回要写入结果文件的组合（可能是最接近的组合）
+///
+/// `records` 是参与搜索的候选行，`pinned` 是固定选中的行，它们的和先从目标值中扣除。
+fn solve(
+    records: &[Record],
+    pinned: &[Record],
+    target: Amount,
+    sizes: RangeInclusive<usize>,
+    solver: &Solver,
+    args: &Args,
+    control: &Arc<SearchControl>,
+) -> Result<TargetResult, String> {
+    let tolerance = parse_tolerance(args, target)?;
+    let remaining = target - sum_of(pinned, args.scale);
+    // 各个目标共用搜索预算，但最接近的组合只能来自这个目标自己的搜索
+    control.reset();
+    let mut found = search(
+        records,
+        remaining,
+        tolerance,
+        sizes.clone(),
+        solver,
+        args,
+        control,
+    )?;
+    // 固定的行本身已经凑够目标时，它们单独就是第一个组合
+    let pinned_only = !pinned.is_empty() && *sizes.start() == 0;
+    if pinned_only && remaining.abs() <= tolerance {
+        found.insert(0, Vec::new());
+        found.truncate(if args.all {
+            args.limit.unwrap_or(usize::MAX)
+        } else {
+            1
+        });
+    }
+    let combinations: Vec<Vec<Record>> = found
+        .iter()
+        .map(|indices| rows_of(records, pinned, indices))
+        .collect();
+
+    let goal = if tolerance == Amount::zero(args.scale) {
+        target.to_string()
+    } else {
+        format!("{} ± {}", target, tolerance)
+    };
+    // 有容差时额外给出实际合计与差额
+    let describe = |combination: &[Record]| {
+        let text = format_combination(combination);
+        if tolerance == Amount::zero(args.scale) {
+            text
+        } else {
+            let sum = sum_of(combination, args.scale);
+            format!("{} (sum {}, diff {})", text, sum, sum - target)
+        }
+    };
+
+    if args.all {
+        println!(
+            "Found {} combination(s) that sum up to {}",
+            combinations.len(),
+            goal
+        );
+        for (i, combination) in combinations.iter().enumerate() {
+            println!("#{}: {}", i + 1, describe(combination));
+        }
+    } else {
+        match combinations.first() {
+            Some(combination) => {
+                println!(
+                    "First combination that sums up to {}: {}",
+                    goal,
+                    describe(combination)
+                )
+            }
+            None => println!("First combination that sums up to {}: None", goal),
+        }
+    }
+
+    // 没有满足条件的组合时，退而输出最接近目标值的组合；
+    // 搜索未完成时也给出目前为止最接近的组合
+    let incomplete = control.stop_reason().is_some();
+    if !combinations.is_empty() || !(args.closest || incomplete) {
+        return Ok(TargetResult {
+            target,
+            combinations,
+            closest: false,
+        });
+    }
+    let mut closest = if args.closest {
+        match solver {
+            Solver::Dp(table) => table.closest(remaining, control),
+            Solver::Mitm => closest_in_the_middle(records, remaining, sizes.clone(), control)?,
+            Solver::Backtrack => {
+                find_closest_combination(records, remaining, sizes.clone(), control)
+            }
+        }
+        .map(|(indices, _)| indices)
+    } else {
+        None
+    };
+    if let Some((best_distance, best)) = control.best().filter(|_| incomplete) {
+        if closest
+            .as_ref()
+            .is_none_or(|c| best_distance < distance(records, c, remaining))
+        {
+            closest = Some(best);
+        }
+    }
+    if pinned_only
+        && closest
+            .as_ref()
+            .is_none_or(|c| remaining.abs().units() < distance(records, c, remaining))
+    {
+        closest = Some(Vec::new());
+    }
+    let combinations = match closest {
+        Some(indices) => {
+            let closest = rows_of(records, pinned, &indices);
+            let sum = sum_of(&closest, args.scale);
+            let label = if control.stop_reason().is_some() {
+                "Closest combination found so far to"
+            } else {
+                "Closest combination to"
+            };
+            println!(
+                "{} {}: {} (sum {}, diff {})",
+                label,
+                target,
+                format_combination(&closest),
+                sum,
+                sum - target
+            );
+            vec![closest]
+        }
+        None => combinations,
+    };
+    Ok(TargetResult {
+        closest: !combinations.is_empty(),
+        target,
+        combinations,
+    })
+}
+
+/// 结果文件的位置，以及其中金额列的名称（取自输入文件的表头）
+struct ResultFile {
+    path: PathBuf,
+    amount_label: String,
+}
+
+/// 逐个目标独立查找组合（同一行可以用于多个目标），结果写入 `output`
+fn solve_targets(
+    candidates: &[Record],
+    pinned: &[Record],
+    targets: &[Amount],
+    sizes: RangeInclusive<usize>,
+    args: &Args,
+    control: &Arc<SearchControl>,
+    output: &ResultFile,
+) -> Result<(), String> {
+    let algorithm = match args.algorithm {
+        Algorithm::Auto => {
+            let chosen = choose_algorithm(
+                candidates,
+                args.all,
+                args.min_items.is_some() || args.max_items.is_some(),
+                args.prefer.is_some(),
+            );
+            if let Some(name) = chosen.to_possible_value() {
+                println!("Using {} algorithm", name.get_name());
+            }
+            chosen
+        }
+        chosen => chosen,
+    };
+    if args.prefer == Some(Prefer::Fewest) && algorithm != Algorithm::Backtrack {
+        return Err(String::from(
+            "--prefer fewest requires the backtrack algorithm",
+        ));
+    }
+
+    // 所有目标共用同一份数据、同一个搜索预算和同一张动态规划表
+    let reporter = (!args.quiet)
+        .then(|| ProgressReporter::start(control.clone(), args.progress, PROGRESS_INTERVAL));
+    let solver = match algorithm {
+        Algorithm::Auto => unreachable!("algorithm is resolved before searching"),
+        Algorithm::Backtrack => Solver::Backtrack,
+        Algorithm::Mitm => Solver::Mitm,
+        Algorithm::Dp => Solver::Dp(dp_table(candidates, args, control)?),
+    };
+    let mut results = Vec::new();
+    for &target in targets {
+        results.push(solve(
+            candidates,
+            pinned,
+            target,
+            sizes.clone(),
+            &solver,
+            args,
+            control,
+        )?);
+    }
+    if let Some(reporter) = reporter {
+        reporter.finish();
+    }
+
+    let written = match results.as_slice() {
+        [result] if !result.combinations.is_empty() => Some(write_combinations_to_csv(
+            &result.combinations,
+            result.target,
+            &output.amount_label,
+            result.closest,
+            output.path.to_str().unwrap_or(""),
+        )),
+        [_] => None,
+        _ => Some(write_targets_to_csv(
+            &results,
+            None,
+            args.scale,
+            output.path.to_str().unwrap_or(""),
+        )),
+    };
+    match written {
+        Some(Ok(_)) => println!("Combination written to {}", output.path.display()),
+        Some(Err(e)) => eprintln!("Failed to write combination to CSV: {}", e),
+        None => {}
+    }
+    Ok(())
+}
+
+/// 为所有目标分配互不相交的组合，报告无法满足的目标和未分配的行，结果写入 `output`
+fn allocate_targets(
+    candidates: &[Record],
+    pinned: &[Record],
+    targets: &[Amount],
+    sizes: RangeInclusive<usize>,
+    args: &Args,
+    control: &Arc<SearchControl>,
+    output: &ResultFile,
+) -> Result<(), String> {
+    if !pinned.is_empty() {
+        return Err(String::from("--disjoint cannot be used with pinned rows"));
+    }
+    if args.all {
+        return Err(String::from("--disjoint cannot be used with --all"));
+    }
+    if !matches!(args.algorithm, Algorithm::Auto | Algorithm::Backtrack)
+        || args.prefer == Some(Prefer::Fewest)
+    {
+        return Err(String::from(
+            "--disjoint always uses backtracking in file order and cannot be combined with --algorithm or --prefer fewest",
+        ));
+    }
+    let goals = targets
+        .iter()
+        .map(|&target| Ok((target, parse_tolerance(args, target)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let reporter = (!args.quiet)
+        .then(|| ProgressReporter::start(control.clone(), args.progress, PROGRESS_INTERVAL));
+    let allocation = allocate(candidates, &goals, sizes, control);
+    if let Some(reporter) = reporter {
+        reporter.finish();
+    }
+
+    println!(
+        "Satisfied {} of {} target(s) with disjoint combinations",
+        allocation.satisfied(),
+        targets.len()
+    );
+    let mut results = Vec::new();
+    for (&target, assignment) in targets.iter().zip(&allocation.assignments) {
+        match assignment {
+            Some(indices) => {
+                let combination = rows_of(candidates, &[], indices);
+                println!("{}: {}", target, format_combination(&combination));
+                results.push(TargetResult {
+                    target,
+                    combinations: vec![combination],
+                    closest: false,
+                });
+            }
+            None => {
+                println!("{}: not satisfied", target);
+                results.push(TargetResult {
+                    target,
+                    combinations: Vec::new(),
+                    closest: false,
+                });
+            }
+        }
+    }
+    let unallocated = rows_of(candidates, &[], &allocation.unallocated);
+    println!(
+        "Unallocated rows ({}, sum {}): {}",
+        unallocated.len(),
+        sum_of(&unallocated, args.scale),
+        format_combination(&unallocated)
+    );
+
+    match write_targets_to_csv(
+        &results,
+        Some(&unallocated),
+        args.scale,
+        output.path.to_str().unwrap_or(""),
+    ) {
+        Ok(_) => println!("Allocation written to {}", output.path.display()),
+        Err(e) => eprintln!("Failed to write allocation to CSV: {}", e),
+    }
+    Ok(())
+}
+
+/// 对账结果文件：每行一条记录，给出所属的组（未匹配的留空）、匹配类型、所在的一边和原始内容
+fn write_reconciliation_to_csv(
+    left: &[Record],
+    right: &[Record],
+    reconciliation: &Reconciliation,
+    output_file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(b',')
+        .from_path(output_file)?;
+    wtr.write_record(["group", "kind", "side", "line", "amount", "row"])?;
+
+    let mut write_rows = |group: &str, kind: &str, side: &str, rows: &[Record]| {
+        rows.iter().try_for_each(|row| {
+            wtr.write_record([
+                group,
+                kind,
+                side,
+                &row.line.to_string(),
+                &row.amount.to_string(),
+                &row.text,
+            ])
+        })
+    };
+    for (i, group) in reconciliation.groups.iter().enumerate() {
+        let id = (i + 1).to_string();
+        let kind = kind_label(group.kind);
+        write_rows(&id, kind, "A", &rows_of(left, &[], &group.left))?;
+        write_rows(&id, kind, "B", &rows_of(right, &[], &group.right))?;
+    }
+    write_rows(
+        "",
+        "unmatched",
+        "A",
+        &rows_of(left, &[], &reconciliation.unmatched_left),
+    )?;
+    write_rows(
+        "",
+        "unmatched",
+        "B",
+        &rows_of(right, &[], &reconciliation.unmatched_right),
+    )?;
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 匹配类型的简写
+fn kind_label(kind: MatchKind) -> &'static str {
+    match kind {
+        MatchKind::OneToOne => "1:1",
+        MatchKind::OneToMany => "1:n",
+        MatchKind::ManyToMany => "n:m",
+    }
+}
+
+/// 执行 `reconcile` 子命令：读取两个文件，输出匹配的组和两边未匹配的行
+fn run_reconcile(args: &ReconcileArgs) -> Result<(), String> {
+    let read = |path: &str| {
+        read_numbers_from_file(path, args.scale, &args.input, None)
+            .map_err(|e| format!("Failed to read numbers from {}: {}", path, e))
+    };
+    let left_table = read(&args.left)?;
+    let right_table = read(&args.right)?;
+    let output_file = output_path(&args.left, "reconciliation.csv")?;
+    report_rejects(
+        &[(&args.left, &left_table), (&args.right, &right_table)],
+        args.input.strict,
+        &output_file.with_file_name("rejects.csv"),
+    )?;
+    let (left, right) = (left_table.records, right_table.records);
+    let tolerance = parse_tolerance_amount(args.tolerance.as_deref(), args.scale)?;
+    let control = start_control(args.timeout, args.max_nodes)?;
+
+    let start_time = Instant::now();
+    let reconciliation = reconcile(&left, &right, tolerance, args.max_group, &control);
+
+    let count = |kind| {
+        reconciliation
+            .groups
+            .iter()
+            .filter(|g| g.kind == kind)
+            .count()
+    };
+    println!(
+        "Matched {} group(s): {} one-to-one, {} one-to-many, {} many-to-many",
+        reconciliation.groups.len(),
+        count(MatchKind::OneToOne),
+        count(MatchKind::OneToMany),
+        count(MatchKind::ManyToMany)
+    );
+    for (i, group) in reconciliation.groups.iter().enumerate() {
+        let a = rows_of(&left, &[], &group.left);
+        let b = rows_of(&right, &[], &group.right);
+        println!(
+            "#{} ({}): A {} = B {}, sum A {}, sum B {}",
+            i + 1,
+            kind_label(group.kind),
+            format_combination(&a),
+            format_combination(&b),
+            sum_of(&a, args.scale),
+            sum_of(&b, args.scale)
+        );
+    }
+    for (side, records, unmatched) in [
+        ("A", &left, &reconciliation.unmatched_left),
+        ("B", &right, &reconciliation.unmatched_right),
+    ] {
+        let rows = rows_of(records, &[], unmatched);
+        println!(
+            "Unmatched in {} ({}, sum {}): {}",
+            side,
+            rows.len(),
+            sum_of(&rows, args.scale),
+            format_combination(&rows)
+        );
+    }
+
+    match write_reconciliation_to_csv(&left, &right, &reconciliation, &output_file) {
+        Ok(_) => println!("Reconciliation written to {}", output_file.display()),
+        Err(e) => eprintln!("Failed to write reconciliation to CSV: {}", e),
+    }
+    println!(
+        "done, elapsed time: {:.2} s.",
+        start_time.elapsed().as_secs_f64()
+    );
+    exit_if_stopped(&control, args.timeout, args.max_nodes);
+    Ok(())
+}
+
+/// 抵消分析的结果文件：每行一条记录，给出所属的组，未抵消的残余行标为 `residue`
+fn write_netting_to_csv(
+    records: &[Record],
+    netting: &Netting,
+    amount_label: &str,
+    output_file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(b',')
+        .from_path(output_file)?;
+    wtr.write_record(["group", "line", amount_label, "row"])?;
+
+    let groups = netting
+        .groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| ((i + 1).to_string(), group))
+        .chain([(String::from("residue"), &netting.residue)]);
+    for (group, indices) in groups {
+        for row in rows_of(records, &[], indices) {
+            wtr.write_record([
+                group.as_str(),
+                &row.line.to_string(),
+                &row.amount.to_string(),
+                &row.text,
+            ])?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// 执行 `netting` 子命令：找出文件中互不相交、和为 0 的组，输出分组报告和残余
+fn run_netting(args: &NettingArgs) -> Result<(), String> {
+    let table = read_numbers_from_file(&args.file, args.scale, &args.input, None)
+        .map_err(|e| format!("Failed to read numbers from file: {}", e))?;
+    let output_file = output_path(&args.file, "netting.csv")?;
+    report_rejects(
+        &[(&args.file, &table)],
+        args.input.strict,
+        &output_file.with_file_name("rejects.csv"),
+    )?;
+    let records = &table.records;
+    let tolerance = parse_tolerance_amount(args.tolerance.as_deref(), args.scale)?;
+    let control = start_control(args.timeout, args.max_nodes)?;
+
+    let start_time = Instant::now();
+    let netting = net(records, tolerance, args.max_group, &control);
+
+    println!(
+        "Found {} zero-sum group(s) covering {} of {} rows",
+        netting.groups.len(),
+        records.len() - netting.residue.len(),
+        records.len()
+    );
+    for (i, group) in netting.groups.iter().enumerate() {
+        let rows = rows_of(records, &[], group);
+        println!(
+            "#{}: {} (sum {})",
+            i + 1,
+            format_combination(&rows),
+            sum_of(&rows, args.scale)
+        );
+    }
+    let residue = rows_of(records, &[], &netting.residue);
+    println!(
+        "Residue ({}, sum {}): {}",
+        residue.len(),
+        sum_of(&residue, args.scale),
+        format_combination(&residue)
+    );
+
+    match write_netting_to_csv(records, &netting, table.amount_label(), &output_file) {
+        Ok(_) => println!("Netting report written to {}", output_file.display()),
+        Err(e) => eprintln!("Failed to write netting report to CSV: {}", e),
+    }
+    println!(
+        "done, elapsed time: {:.2} s.",
+        start_time.elapsed().as_secs_f64()
+    );
+    exit_if_stopped(&control, args.timeout, args.max_nodes);
+    Ok(())
+}
+
+/// 结果文件的路径：与输入文件放在同一目录下
+fn output_path(file_path: &str, name: &str) -> Result<PathBuf, String> {
     // 获取绝对路径
-    let absolute_file_path = match canonicalize(&file_path) {
+    let absolute_file_path = match canonicalize(file_path) {
         Ok(abs_path) => abs_path,
-        Err(_) => PathBuf::from(&file_path),
+        Err(_) => PathBuf::from(file_path),
     };
 
     // 获取父路径
-    let parent_path = match absolute_file_path.parent() {
-        Some(parent) => parent,
-        None => {
-            eprintln!("Failed to get parent directory of the file.");
-            return;
+    match absolute_file_path.parent() {
+        Some(parent) => Ok(parent.join(name)),
+        None => Err(String::from("Failed to get parent directory of the file.")),
+    }
+}
+
+/// 按 `--timeout` 和 `--max-nodes` 创建搜索控制，并让 Ctrl-C 请求停止
+fn start_control(
+    timeout: Option<f64>,
+    max_nodes: Option<u64>,
+) -> Result<Arc<SearchControl>, String> {
+    let mut control = SearchControl::new();
+    if let Some(seconds) = timeout {
+        let timeout = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| format!("Invalid timeout `{}`", seconds))?;
+        control = control.timeout(timeout);
+    }
+    if let Some(max_nodes) = max_nodes {
+        control = control.max_nodes(max_nodes);
+    }
+    let control = Arc::new(control);
+
+    // 第一次 Ctrl-C 让求解器停下并输出已找到的组合，再按一次直接退出
+    let handler_control = control.clone();
+    if let Err(e) = ctrlc::set_handler(move || {
+        if handler_control.stop_reason() == Some(StopReason::Interrupted) {
+            process::exit(EXIT_INTERRUPTED);
+        }
+        handler_control.stop(StopReason::Interrupted);
+    }) {
+        eprintln!("Failed to install Ctrl-C handler: {}", e);
+    }
+    Ok(control)
+}
+
+/// 搜索被提前停止时输出原因和进度（覆盖比例能估计时），并以相应的退出码结束进程
+fn exit_if_stopped(control: &SearchControl, timeout: Option<f64>, max_nodes: Option<u64>) {
+    if let Some(reason) = control.stop_reason() {
+        let (summary, code) = match reason {
+            StopReason::Timeout => (
+                format!(
+                    "Search incomplete: time limit of {} s reached",
+                    timeout.unwrap_or(0.0)
+                ),
+                EXIT_INCOMPLETE,
+            ),
+            StopReason::NodeLimit => (
+                format!(
+                    "Search incomplete: node limit of {} reached",
+                    max_nodes.unwrap_or(0)
+                ),
+                EXIT_INCOMPLETE,
+            ),
+            StopReason::Interrupted => (String::from("Search interrupted"), EXIT_INTERRUPTED),
+        };
+        let explored = control.explored() * 100.0;
+        let explored = if explored > 0.0 && explored < 0.01 {
+            String::from("less than 0.01%")
+        } else {
+            format!("about {:.2}%", explored)
+        };
+        if !control.explored_known() {
+            eprintln!("{} after {} nodes", summary, control.nodes());
+        } else {
+            eprintln!(
+                "{} after {} nodes, explored {} of the search space",
+                summary,
+                control.nodes(),
+                explored
+            );
+        }
+        process::exit(code);
+    }
+}
+
+/// 输出错误信息并以 `EXIT_ERROR` 退出
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(EXIT_ERROR);
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Some(command) = &args.command {
+        let outcome = match command {
+            Command::Reconcile(reconcile_args) => run_reconcile(reconcile_args),
+            Command::Netting(netting_args) => run_netting(netting_args),
+        };
+        if let Err(e) = outcome {
+            fail(e);
+        }
+        return;
+    }
+
+    let file_path = args.file.clone().unwrap_or_else(|| {
+        args.file_pos
+            .clone()
+            .expect("--file or FILE_PATH is required")
+    });
+    let targets = match parse_targets(&args) {
+        Ok(targets) => targets,
+        Err(e) => {
+            fail(e);
+        }
+    };
+    if targets.len() > 1 && args.all {
+        fail("--all cannot be used with more than one target");
+    }
+    let sizes = match parse_sizes(&args) {
+        Ok(sizes) => sizes,
+        Err(e) => {
+            fail(e);
         }
     };
 
-    let output_file = parent_path.join("result.csv");
+    let output_file = match output_path(&file_path, "result.csv") {
+        Ok(path) => path,
+        Err(e) => {
+            fail(e);
+        }
+    };
 
     let start_time = Instant::now();
 
-    match read_numbers_from_file(&absolute_file_path.to_str().unwrap_or(&file_path)) {
-        Ok(nums) => {
-            let first_combination = find_first_combination(&nums, target);
-            println!(
-                "First combination that sums up to {}: {:?}",
-                target, first_combination
-            );
+    let control = match start_control(args.timeout, args.max_nodes) {
+        Ok(control) => control,
+        Err(e) => {
+            fail(e);
+        }
+    };
 
-            if let Some(combination) = first_combination {
-                match write_combinations_to_csv(&[combination], &output_file.to_str().unwrap_or(""))
-                {
-                    Ok(_) => println!("Combination written to {}", output_file.display()),
-                    Err(e) => eprintln!("Failed to write combination to CSV: {}", e),
-                }
-            }
+    let table = match read_numbers_from_file(
+        &file_path,
+        args.scale,
+        &args.input,
+        args.flag_column.as_ref(),
+    ) {
+        Ok(table) => table,
+        Err(e) => {
+            fail(format!("Failed to read numbers from file: {}", e));
         }
-        Err(e) => eprintln!("Failed to read numbers from file: {}", e),
+    };
+    let rejects_file = output_file.with_file_name("rejects.csv");
+    if let Err(e) = report_rejects(&[(&file_path, &table)], args.input.strict, &rejects_file) {
+        fail(e);
+    }
+
+    let records = &table.records;
+    let output = ResultFile {
+        path: output_file,
+        amount_label: table.amount_label().to_string(),
+    };
+
+    // 固定选中的行不参与搜索，从目标值中扣除；排除的行直接去掉
+    let flag_field = match args.flag_column.as_ref().map(|c| table.field_index(c)) {
+        Some(Err(e)) => {
+            fail(format!("Invalid --flag-column: {}", e));
+        }
+        flag => flag.and_then(Result::ok),
+    };
+    let selection =
+        match RowSelection::new(records, &args.include_rows, &args.exclude_rows, flag_field) {
+            Ok(selection) => selection,
+            Err(e) => {
+                fail(e);
+            }
+        };
+    let pinned: Vec<Record> = selection
+        .pinned
+        .iter()
+        .map(|&i| records[i].clone())
+        .collect();
+    if !selection.is_empty() {
+        println!(
+            "Pinned {} row(s) summing to {}, excluded {} row(s)",
+            pinned.len(),
+            sum_of(&pinned, args.scale),
+            selection.excluded.len()
+        );
+    }
+    if pinned.len() > *sizes.end() {
+        fail("More rows are pinned than --max-items allows");
+    }
+    let sizes = sizes.start().saturating_sub(pinned.len())..=sizes.end() - pinned.len();
+    let mut candidates: Vec<Record> = selection
+        .candidates(records)
+        .iter()
+        .map(|&i| records[i].clone())
+        .collect();
+
+    // 先搜索绝对值大的金额，回溯顺序中靠前的组合就由大额组成
+    if args.prefer == Some(Prefer::Largest) {
+        candidates.sort_by_key(|r| Reverse(r.amount.abs()));
+    }
+    let outcome = if args.disjoint {
+        allocate_targets(
+            &candidates,
+            &pinned,
+            &targets,
+            sizes,
+            &args,
+            &control,
+            &output,
+        )
+    } else {
+        solve_targets(
+            &candidates,
+            &pinned,
+            &targets,
+            sizes,
+            &args,
+            &control,
+            &output,
+        )
+    };
+    if let Err(e) = outcome {
+        fail(e);
     }
 
     let end_time = Instant::now();
     let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
     let runtime = format!("{elapsed_time:.2}");
     println!("done, elapsed time: {} s.", runtime);
-}
\ No newline at end of file
+
+    exit_if_stopped(&control, args.timeout, args.max_nodes);
+}
@@ -0,0 +1,172 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+use std::ops::RangeInclusive;
+
+/// 折半搜索支持的最大条目数，右半部分需要 2^(n/2) 个元素的有序表
+pub const MAX_MITM_ITEMS: usize = 44;
+
+/// 枚举 `values` 所有子集的和，下标即子集的位掩码
+fn subset_sums(values: &[i64]) -> Vec<i64> {
+    let mut sums = vec![0i64; 1 << values.len()];
+    for mask in 1..sums.len() {
+        let lowest = mask.trailing_zeros() as usize;
+        sums[mask] = sums[mask & (mask - 1)] + values[lowest];
+    }
+    sums
+}
+
+/// 条目过多时右半部分的表放不下
+fn check_len(len: usize) -> Result<(), String> {
+    if len > MAX_MITM_ITEMS {
+        return Err(format!(
+            "meet-in-the-middle supports at most {} amounts, got {}",
+            MAX_MITM_ITEMS, len
+        ));
+    }
+    Ok(())
+}
+
+/// `values` 所有子集的和及其位掩码，按和排序
+fn sorted_sums(values: &[i64]) -> Vec<(i64, u64)> {
+    let mut sums: Vec<(i64, u64)> = subset_sums(values)
+        .into_iter()
+        .enumerate()
+        .map(|(mask, sum)| (sum, mask as u64))
+        .collect();
+    sums.sort_unstable();
+    sums
+}
+
+/// 把位掩码还原成下标列表，`offset` 为该半边在输入中的起始下标
+fn mask_indices(mask: u64, offset: usize, out: &mut Vec<usize>) {
+    let mut mask = mask;
+    while mask != 0 {
+        out.push(offset + mask.trailing_zeros() as usize);
+        mask &= mask - 1;
+    }
+}
+
+/// 折半搜索（Horowitz–Sahni）：把输入分成两半，分别枚举子集和，
+/// 对右半部分排序后，为左半部分的每个和二分查找能凑进 `[target - tolerance, target + tolerance]` 的右半和
+///
+/// 时间复杂度 O(2^(n/2) · n)，正负混合和定点金额都可直接处理。
+/// 只保留元素个数在 `sizes` 范围内的组合，
+/// 最多返回 `limit` 个，每个组合是升序的下标列表；条目过多时返回错误。
+/// 搜索预算用尽时返回已经找到的组合。
+pub fn meet_in_the_middle<T: HasAmount>(
+    items: &[T],
+    target: Amount,
+    tolerance: Amount,
+    sizes: RangeInclusive<usize>,
+    limit: usize,
+    control: &SearchControl,
+) -> Result<Vec<Vec<usize>>, String> {
+    check_len(items.len())?;
+    let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+    let (left, right) = values.split_at(values.len() / 2);
+    let low = target.units() - tolerance.units().abs();
+    let high = target.units() + tolerance.units().abs();
+
+    let left_sums = subset_sums(left);
+    let right_sums = sorted_sums(right);
+
+    let mut results = Vec::new();
+    let total = left_sums.len() as f64;
+    let mut processed = 0;
+    let mut reported = 0;
+    for (left_mask, &left_sum) in left_sums.iter().enumerate() {
+        // 每处理一批左半子集更新一次进度并检查预算
+        if left_mask % 1024 == 1023 {
+            control.add_explored((left_mask - reported) as f64 / total);
+            reported = left_mask;
+            if control.tick(1024) {
+                break;
+            }
+        }
+        processed = left_mask + 1;
+
+        let start = right_sums.partition_point(|&(sum, _)| sum < low - left_sum);
+        for &(sum, right_mask) in &right_sums[start..] {
+            if sum > high - left_sum || results.len() >= limit {
+                break;
+            }
+            // 两边都不选是空集，不算解
+            if left_mask == 0 && right_mask == 0 {
+                continue;
+            }
+            let size = (left_mask.count_ones() + right_mask.count_ones()) as usize;
+            if !sizes.contains(&size) {
+                continue;
+            }
+            let mut indices = Vec::new();
+            mask_indices(left_mask as u64, 0, &mut indices);
+            mask_indices(right_mask, left.len(), &mut indices);
+            results.push(indices);
+            control.add_solutions(1);
+        }
+        if results.len() >= limit {
+            break;
+        }
+    }
+    control.add_explored((processed - reported) as f64 / total);
+    Ok(results)
+}
+
+/// 用折半搜索找和最接近 `target` 且元素个数在 `sizes` 范围内的组合，返回升序下标和差额
+///
+/// 右半部分的子集和按元素个数分组排序，左半部分的每个和只需在各组中二分查找
+/// `target - left_sum` 两侧的相邻值，复杂度与精确查找相同。
+/// 搜索预算用尽时返回目前找到的最接近的组合；条目过多时返回错误。
+pub fn closest_in_the_middle<T: HasAmount>(
+    items: &[T],
+    target: Amount,
+    sizes: RangeInclusive<usize>,
+    control: &SearchControl,
+) -> Result<Option<(Vec<usize>, Amount)>, String> {
+    check_len(items.len())?;
+    let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+    let (left, right) = values.split_at(values.len() / 2);
+    let target_units = target.units();
+
+    let left_sums = subset_sums(left);
+    let mut right_by_size: Vec<Vec<(i64, u64)>> = vec![Vec::new(); right.len() + 1];
+    for (sum, mask) in sorted_sums(right) {
+        right_by_size[mask.count_ones() as usize].push((sum, mask));
+    }
+
+    // (距离, 左半掩码, 右半掩码)
+    let mut best: Option<(i64, usize, u64)> = None;
+    for (left_mask, &left_sum) in left_sums.iter().enumerate() {
+        if left_mask % 1024 == 1023 && control.tick(1024) {
+            break;
+        }
+        let left_size = left_mask.count_ones() as usize;
+        let want = target_units - left_sum;
+        for (right_size, right_sums) in right_by_size.iter().enumerate() {
+            // 两边都不选是空集，不算组合
+            if !sizes.contains(&(left_size + right_size)) || left_mask == 0 && right_size == 0 {
+                continue;
+            }
+            let at = right_sums.partition_point(|&(sum, _)| sum < want);
+            let neighbours = right_sums[at.saturating_sub(1)..right_sums.len().min(at + 1)].iter();
+            for &(sum, right_mask) in neighbours {
+                let distance = (left_sum + sum - target_units).abs();
+                if best.is_none_or(|(d, _, _)| distance < d) {
+                    best = Some((distance, left_mask, right_mask));
+                }
+            }
+        }
+        if best.is_some_and(|(d, _, _)| d == 0) {
+            break;
+        }
+    }
+
+    Ok(best.map(|(_, left_mask, right_mask)| {
+        let mut indices = Vec::new();
+        mask_indices(left_mask as u64, 0, &mut indices);
+        mask_indices(right_mask, left.len(), &mut indices);
+        let sum: i64 = indices.iter().map(|&i| values[i]).sum();
+        (indices, Amount::from_units(sum, target.scale()) - target)
+    }))
+}
@@ -0,0 +1,138 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// 每访问这么多个节点检查一次搜索预算
+const CHECK_INTERVAL: u64 = 1024;
+
+/// 抵消分析的结果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Netting {
+    /// 互不相交、和为 0 的组（升序下标），按组的大小排列
+    pub groups: Vec<Vec<usize>>,
+    /// 没有被任何组抵消掉的行
+    pub residue: Vec<usize>,
+}
+
+/// 从带符号的分录中反复取出互不相交、和为 0（相差不超过 `tolerance`）的组，小组优先
+///
+/// 金额为 0（在容差以内）的行（例如作废或冲销的分录）先各自单独成组，
+/// 再找两行的组、三行的，直到 `max_group` 行。同样大小的组按下标的字典序贪心选取，
+/// 与回溯搜索的输出顺序一致。组的最后一行通过按金额建立的索引直接查找，
+/// 找 k 行的组只需枚举前 k - 1 行。
+/// 搜索预算用尽时停止，剩下的行计入残余。
+pub fn net<T: HasAmount>(
+    items: &[T],
+    tolerance: Amount,
+    max_group: usize,
+    control: &Arc<SearchControl>,
+) -> Netting {
+    control.set_explored_unknown();
+    let mut finder = GroupFinder::new(items, tolerance, control);
+    let mut groups = Vec::new();
+
+    for size in 1..=max_group.min(items.len()) {
+        control.set_depth(size);
+        // 字典序更小的组在之前的查找中已经排除，取出行之后也不会出现新的，下一次从上一组的首行之后开始
+        let mut start = 0;
+        let mut group = Vec::with_capacity(size);
+        while finder.find(start, size, 0, &mut group) {
+            start = group[0] + 1;
+            finder.take(&group);
+            control.add_solutions(1);
+            groups.push(std::mem::take(&mut group));
+        }
+        if control.stop_reason().is_some() {
+            break;
+        }
+    }
+
+    Netting {
+        groups,
+        residue: (0..items.len()).filter(|&i| !finder.used[i]).collect(),
+    }
+}
+
+/// 在尚未取出的行中查找和为 0 的组
+struct GroupFinder<'a> {
+    values: Vec<i64>,
+    tolerance: i64,
+    used: Vec<bool>,
+    /// 金额到尚未取出的行（升序下标）的索引
+    by_value: BTreeMap<i64, Vec<usize>>,
+    control: &'a SearchControl,
+    steps: u64,
+}
+
+impl<'a> GroupFinder<'a> {
+    fn new<T: HasAmount>(items: &[T], tolerance: Amount, control: &'a SearchControl) -> Self {
+        let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+        let mut by_value: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+        for (i, &v) in values.iter().enumerate() {
+            by_value.entry(v).or_default().push(i);
+        }
+        GroupFinder {
+            used: vec![false; values.len()],
+            values,
+            tolerance: tolerance.units().abs(),
+            by_value,
+            control,
+            steps: 0,
+        }
+    }
+
+    /// 找首行不小于 `start`、共 `size` 行、与已选行之和 `sum` 合起来为 0 的字典序最小的组，
+    /// 找到时追加到 `group` 并返回 `true`；搜索预算用尽时返回 `false`
+    fn find(&mut self, start: usize, size: usize, sum: i64, group: &mut Vec<usize>) -> bool {
+        if size == 1 {
+            return match self.last(start, sum) {
+                Some(i) => {
+                    group.push(i);
+                    true
+                }
+                None => false,
+            };
+        }
+        for i in start..self.values.len() {
+            if self.used[i] {
+                continue;
+            }
+            self.steps += 1;
+            if self.steps.is_multiple_of(CHECK_INTERVAL) && self.control.tick(CHECK_INTERVAL) {
+                return false;
+            }
+            group.push(i);
+            if self.find(i + 1, size - 1, sum + self.values[i], group) {
+                return true;
+            }
+            group.pop();
+            if self.control.stop_reason().is_some() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// 下标不小于 `start`、能把 `sum` 抵消到容差以内的最小下标
+    fn last(&self, start: usize, sum: i64) -> Option<usize> {
+        self.by_value
+            .range(-sum - self.tolerance..=-sum + self.tolerance)
+            .filter_map(|(_, rows)| rows.get(rows.partition_point(|&i| i < start)).copied())
+            .min()
+    }
+
+    /// 取出一组行，之后的查找不再使用
+    fn take(&mut self, group: &[usize]) {
+        for &i in group {
+            self.used[i] = true;
+            if let Some(rows) = self.by_value.get_mut(&self.values[i]) {
+                rows.retain(|&r| r != i);
+                if rows.is_empty() {
+                    self.by_value.remove(&self.values[i]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::search::SubsetSumSolutions;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 按前几个元素的取舍切分搜索树时最多固定的元素个数
+const MAX_SPLIT_DEPTH: usize = 12;
+
+/// 子树中按回溯顺序可能出现的最小组合，用于决定处理顺序和何时可以停止
+///
+/// 子树中的组合都以固定前缀开头，之后只能接下标不小于 `depth` 的元素。
+fn smallest_possible(chosen: &[usize], depth: usize) -> Vec<usize> {
+    if chosen.is_empty() {
+        vec![depth]
+    } else {
+        chosen.to_vec()
+    }
+}
+
+/// 在线程池上并行回溯，返回 `solutions` 的前 `limit` 个组合（升序下标）
+///
+/// `solutions` 是设置好目标、容差、元素个数和搜索控制但尚未开始迭代的搜索，
+/// 前 `depth` 个元素的每种取舍构成一棵独立的子树，各自克隆一份（共用同一张边界表）交给 rayon 的工作窃取线程池。
+/// `limit` 为 1 且不要求 `ordered` 时任一线程找到解就通过共享标志让其他线程停下；
+/// 否则按子树中可能的最小组合排序后分批处理，合并、去重并排序，结果与单线程完全一致。
+pub fn parallel_solutions(
+    solutions: SubsetSumSolutions,
+    limit: usize,
+    threads: usize,
+    ordered: bool,
+) -> Result<Vec<Vec<usize>>, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| format!("Failed to start thread pool: {}", e))?;
+
+    // 子树数量取线程数的若干倍，便于负载均衡
+    let batch = pool.current_num_threads() * 4;
+    let depth = ((batch * 4).next_power_of_two().trailing_zeros() as usize)
+        .min(MAX_SPLIT_DEPTH)
+        .min(solutions.item_count());
+    let mut prefixes: Vec<Vec<usize>> = (0..1usize << depth)
+        .map(|mask| (0..depth).filter(|i| mask & (1 << i) != 0).collect())
+        .collect();
+    prefixes.sort_by_cached_key(|chosen| smallest_possible(chosen, depth));
+    let found = Arc::new(AtomicBool::new(false));
+    let solutions = solutions.share_bounds();
+
+    let search = |chosen: &[usize], limit: usize| -> Vec<Vec<usize>> {
+        solutions
+            .clone()
+            .subtree(chosen, depth)
+            .stop_on(found.clone())
+            .take(limit)
+            .collect()
+    };
+
+    let mut results: Vec<Vec<usize>> = if limit == 1 && !ordered {
+        pool.install(|| {
+            prefixes
+                .par_iter()
+                .flat_map_iter(|chosen| {
+                    if found.load(Ordering::Relaxed) {
+                        return Vec::new();
+                    }
+                    let solutions = search(chosen, 1);
+                    if !solutions.is_empty() {
+                        found.store(true, Ordering::Relaxed);
+                    }
+                    solutions
+                })
+                .collect()
+        })
+    } else {
+        // 每棵子树内部按回溯顺序产生解，取前 `limit` 个就足够；
+        // 已有的第 `limit` 个解比剩余子树可能的最小组合还靠前时即可停止
+        let mut results: Vec<Vec<usize>> = Vec::new();
+        for chunk in prefixes.chunks(batch) {
+            if results.len() >= limit {
+                let next = smallest_possible(&chunk[0], depth);
+                if results[limit - 1] < next {
+                    break;
+                }
+            }
+            let batch_results: Vec<Vec<Vec<usize>>> = pool.install(|| {
+                chunk
+                    .par_iter()
+                    .map(|chosen| search(chosen, limit))
+                    .collect()
+            });
+            results.extend(batch_results.into_iter().flatten());
+            results.sort();
+            results.truncate(limit);
+        }
+        results
+    };
+
+    results.sort();
+    results.dedup();
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// 取 `solutions` 的前 `limit` 个组合，`threads` 不为 1 时在线程池上并行，参见 `parallel_solutions`
+pub fn find_solutions(
+    solutions: SubsetSumSolutions,
+    limit: usize,
+    threads: usize,
+    ordered: bool,
+) -> Result<Vec<Vec<usize>>, String> {
+    if threads != 1 {
+        return parallel_solutions(solutions, limit, threads, ordered);
+    }
+    Ok(solutions.take(limit).collect())
+}
+
+/// 迭代加深：按元素个数从少到多逐轮搜索，返回 `solutions` 中元素最少的前 `limit` 个组合
+///
+/// 每轮只接受一种元素个数，轮内按回溯顺序，因此结果按元素个数不减排列，多线程时也与单线程一致。
+/// 边界表只建一次，各轮共用。
+/// 每轮约占整体进度的相同份额；搜索被提前停止时返回已经找到的组合。
+pub fn fewest_first(
+    solutions: SubsetSumSolutions,
+    limit: usize,
+    threads: usize,
+) -> Result<Vec<Vec<usize>>, String> {
+    let sizes = solutions.sizes();
+    let first = (*sizes.start()).max(1);
+    let last = (*sizes.end()).min(solutions.item_count());
+    let control = solutions.search_control().cloned();
+    // 按个数收紧的边界表覆盖到倒数第二轮即可，取全部元素的那一轮用不上它
+    let depth = last
+        .min(solutions.item_count().saturating_sub(1))
+        .max(first);
+    let solutions = solutions.items(first..=depth).share_bounds();
+    if let Some(control) = &control {
+        control.pass_weight(1.0 / (last + 1).saturating_sub(first).max(1) as f64);
+    }
+    let mut found = Vec::new();
+    for size in first..=last {
+        let stopped = control.as_ref().is_some_and(|c| c.stop_reason().is_some());
+        if found.len() >= limit || stopped {
+            break;
+        }
+        let pass = solutions.clone().items(size..=size);
+        found.extend(find_solutions(pass, limit - found.len(), threads, true)?);
+    }
+    Ok(found)
+}
@@ -0,0 +1,93 @@
+use crate::control::SearchControl;
+use clap::ValueEnum;
+use std::io::Write;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// 进度输出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// 在后台线程中定期把搜索进度写到标准错误
+///
+/// 每隔 `interval` 读取一次共享的搜索控制，输出已访问节点数、当前深度、已找到的组合数、
+/// 估计的搜索空间覆盖比例和已用时间。在第一个间隔内结束的搜索不会产生任何输出。
+pub struct ProgressReporter {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    pub fn start(control: Arc<SearchControl>, format: ProgressFormat, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            // 发送端被丢弃时 `recv_timeout` 立即返回 `Disconnected`
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let line = format_progress(&control, format);
+                let _ = writeln!(std::io::stderr().lock(), "{}", line);
+            }
+        });
+        ProgressReporter {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// 停止输出并等待后台线程退出
+    pub fn finish(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// 没有调用 `finish` 就提前返回（例如出错）时同样停止后台线程
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 把当前进度格式化为一行；JSON 格式的各个键及其顺序供外部脚本解析
+///
+/// 无法估计覆盖比例时（见 `SearchControl::explored_known`），JSON 中 `explored` 为 `null`，
+/// 文本中省略这一项。
+pub fn format_progress(control: &SearchControl, format: ProgressFormat) -> String {
+    let elapsed = control.elapsed().as_secs_f64();
+    let explored = control.explored_known().then(|| control.explored());
+    match format {
+        ProgressFormat::Text => {
+            let mut line = format!(
+                "[{:.1} s] nodes {}, depth {}, solutions {}",
+                elapsed,
+                control.nodes(),
+                control.depth(),
+                control.solutions()
+            );
+            if let Some(explored) = explored {
+                line.push_str(&format!(", explored {:.2}%", explored * 100.0));
+            }
+            line
+        }
+        ProgressFormat::Json => format!(
+            "{{\"elapsed\":{:.3},\"nodes\":{},\"depth\":{},\"solutions\":{},\"explored\":{}}}",
+            elapsed,
+            control.nodes(),
+            control.depth(),
+            control.solutions(),
+            explored.map_or(String::from("null"), |e| format!("{:.6}", e))
+        ),
+    }
+}
@@ -0,0 +1,173 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+use crate::search::SubsetSumSolutions;
+use std::sync::Arc;
+
+/// 匹配组的类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// 两边各一行
+    OneToOne,
+    /// 一边一行，另一边多行
+    OneToMany,
+    /// 两边都是多行
+    ManyToMany,
+}
+
+/// 两边金额之和相等的一组行（各自的升序下标）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchGroup {
+    pub kind: MatchKind,
+    pub left: Vec<usize>,
+    pub right: Vec<usize>,
+}
+
+/// 两个文件的对账结果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub groups: Vec<MatchGroup>,
+    pub unmatched_left: Vec<usize>,
+    pub unmatched_right: Vec<usize>,
+}
+
+/// 对账：在两边找出和相等（相差不超过 `tolerance`）的行组，每行最多属于一个组
+///
+/// 依次进行一对一、一对多（包括多对一）和多对多匹配，每一步只在前面剩下的行中查找，
+/// 组中每一边最多 `max_group` 行。多对多匹配把右边取反后与左边合在一起找和为 0 的组合。
+/// 搜索预算用尽时停止后续匹配，剩下的行计入未匹配。
+pub fn reconcile<T: HasAmount>(
+    left: &[T],
+    right: &[T],
+    tolerance: Amount,
+    max_group: usize,
+    control: &Arc<SearchControl>,
+) -> Reconciliation {
+    control.set_explored_unknown();
+    let left: Vec<Amount> = left.iter().map(|n| n.amount()).collect();
+    let right: Vec<Amount> = right.iter().map(|n| n.amount()).collect();
+    let tolerance = tolerance.abs();
+    let mut left_used = vec![false; left.len()];
+    let mut right_used = vec![false; right.len()];
+    let mut groups = Vec::new();
+
+    // 一对一：在所有差额不超过容差的行对中，按差额从小到大（相同时按下标）贪心选取，
+    // 精确相等的行对总是先于只在容差以内的行对
+    let mut by_amount: Vec<usize> = (0..right.len()).collect();
+    by_amount.sort_by_key(|&j| right[j]);
+    let mut pairs = Vec::new();
+    for (i, &a) in left.iter().enumerate() {
+        let start = by_amount.partition_point(|&j| right[j] < a - tolerance);
+        for &j in by_amount[start..]
+            .iter()
+            .take_while(|&&j| right[j] <= a + tolerance)
+        {
+            pairs.push(((right[j] - a).abs(), i, j));
+        }
+    }
+    pairs.sort_unstable();
+    for (_, i, j) in pairs {
+        if left_used[i] || right_used[j] {
+            continue;
+        }
+        left_used[i] = true;
+        right_used[j] = true;
+        groups.push(MatchGroup {
+            kind: MatchKind::OneToOne,
+            left: vec![i],
+            right: vec![j],
+        });
+    }
+    groups.sort_by_key(|group| group.left[0]);
+
+    // 一对多：左边一行对右边多行，再反过来
+    for flipped in [false, true] {
+        let (ones, manys) = if flipped {
+            (&right, &left)
+        } else {
+            (&left, &right)
+        };
+        for i in 0..ones.len() {
+            let (ones_used, manys_used) = if flipped {
+                (&right_used, &left_used)
+            } else {
+                (&left_used, &right_used)
+            };
+            if ones_used[i] || control.stop_reason().is_some() {
+                continue;
+            }
+            let available: Vec<usize> = (0..manys.len()).filter(|&j| !manys_used[j]).collect();
+            let values: Vec<Amount> = available.iter().map(|&j| manys[j]).collect();
+            let found = SubsetSumSolutions::new(&values, ones[i])
+                .tolerance(tolerance)
+                .items(2..=max_group)
+                .control(control.clone())
+                .next();
+            if let Some(indices) = found {
+                let many: Vec<usize> = indices.iter().map(|&k| available[k]).collect();
+                let (left_rows, right_rows) = if flipped {
+                    (many, vec![i])
+                } else {
+                    (vec![i], many)
+                };
+                mark(&mut left_used, &left_rows);
+                mark(&mut right_used, &right_rows);
+                groups.push(MatchGroup {
+                    kind: MatchKind::OneToMany,
+                    left: left_rows,
+                    right: right_rows,
+                });
+            }
+        }
+    }
+
+    // 多对多：左边为正、右边取反，找两边都至少两行且和为 0 的组合
+    while control.stop_reason().is_none() {
+        let available_left: Vec<usize> = (0..left.len()).filter(|&i| !left_used[i]).collect();
+        let available_right: Vec<usize> = (0..right.len()).filter(|&j| !right_used[j]).collect();
+        let values: Vec<Amount> = available_left
+            .iter()
+            .map(|&i| left[i])
+            .chain(available_right.iter().map(|&j| -right[j]))
+            .collect();
+        let split = available_left.len();
+        let found = SubsetSumSolutions::new(&values, Amount::zero(tolerance.scale()))
+            .tolerance(tolerance)
+            .items(4..=max_group.saturating_mul(2))
+            .control(control.clone())
+            .find(|indices| {
+                let on_left = indices.iter().filter(|&&k| k < split).count();
+                let on_right = indices.len() - on_left;
+                (2..=max_group).contains(&on_left) && (2..=max_group).contains(&on_right)
+            });
+        let Some(indices) = found else {
+            break;
+        };
+        let (left_part, right_part): (Vec<usize>, Vec<usize>) =
+            indices.iter().partition(|&&k| k < split);
+        let left_rows: Vec<usize> = left_part.iter().map(|&k| available_left[k]).collect();
+        let right_rows: Vec<usize> = right_part
+            .iter()
+            .map(|&k| available_right[k - split])
+            .collect();
+        mark(&mut left_used, &left_rows);
+        mark(&mut right_used, &right_rows);
+        groups.push(MatchGroup {
+            kind: MatchKind::ManyToMany,
+            left: left_rows,
+            right: right_rows,
+        });
+    }
+
+    Reconciliation {
+        groups,
+        unmatched_left: (0..left.len()).filter(|&i| !left_used[i]).collect(),
+        unmatched_right: (0..right.len()).filter(|&j| !right_used[j]).collect(),
+    }
+}
+
+fn mark(used: &mut [bool], rows: &[usize]) {
+    for &i in rows {
+        used[i] = true;
+    }
+}
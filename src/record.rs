@@ -0,0 +1,31 @@
+use crate::amount::Amount;
+
+/// 输入文件中的一行数据
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// 行号，从 1 开始
+    pub line: usize,
+    /// 解析出的金额
+    pub amount: Amount,
+    /// 原始行内容
+    pub text: String,
+    /// 金额之外的其余列
+    pub fields: Vec<String>,
+}
+
+/// 可参与搜索的条目，只要能给出金额即可
+pub trait HasAmount {
+    fn amount(&self) -> Amount;
+}
+
+impl HasAmount for Amount {
+    fn amount(&self) -> Amount {
+        *self
+    }
+}
+
+impl HasAmount for Record {
+    fn amount(&self) -> Amount {
+        self.amount
+    }
+}
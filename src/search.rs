@@ -0,0 +1,500 @@
+use crate::amount::Amount;
+use crate::control::SearchControl;
+use crate::record::HasAmount;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 每走这么多步检查一次停止标志和搜索预算，避免频繁读取原子变量
+const CHECK_INTERVAL: u32 = 1024;
+
+/// 只取有限个元素的两张边界表合计最多占用的条目数（8 MiB 的 i64）
+const MAX_TOP_ENTRIES: usize = 1 << 20;
+
+/// 后缀和边界：`pos[i]`/`neg[i]` 分别是 `nums[i..]` 中正数之和与负数之和
+///
+/// 从下标 `i` 起任取若干元素，其和必然落在 `[neg[i], pos[i]]` 区间内，
+/// 因此无论输入正负混合与否，用它剪枝都不会漏掉任何解。
+/// 限制了组合的元素个数时，另外记录后缀中最大的 k 个正数之和与最小的 k 个负数之和，边界更紧。
+pub struct SuffixBounds {
+    pos: Vec<i64>,
+    neg: Vec<i64>,
+    /// `pos_top[i * (depth + 1) + k]`/`neg_top[...]` 为 `nums[i..]` 中最多取 k 个元素时的上下界，
+    /// 未限制元素个数时为空
+    depth: usize,
+    pos_top: Vec<i64>,
+    neg_top: Vec<i64>,
+}
+
+impl SuffixBounds {
+    pub fn new(nums: &[i64]) -> Self {
+        let mut pos = vec![0; nums.len() + 1];
+        let mut neg = vec![0; nums.len() + 1];
+        for (i, &n) in nums.iter().enumerate().rev() {
+            pos[i] = pos[i + 1] + n.max(0);
+            neg[i] = neg[i + 1] + n.min(0);
+        }
+        SuffixBounds {
+            pos,
+            neg,
+            depth: 0,
+            pos_top: Vec::new(),
+            neg_top: Vec::new(),
+        }
+    }
+
+    /// 组合最多包含 `max_items` 个元素时的边界，表太大时退化为不限个数的边界
+    pub fn with_max_items(nums: &[i64], max_items: usize) -> Self {
+        let mut bounds = SuffixBounds::new(nums);
+        let depth = max_items.min(nums.len());
+        let stride = depth + 1;
+        if depth == nums.len()
+            || (nums.len() + 1).saturating_mul(stride).saturating_mul(2) > MAX_TOP_ENTRIES
+        {
+            return bounds;
+        }
+
+        let mut pos_top = vec![0; (nums.len() + 1) * stride];
+        let mut neg_top = vec![0; (nums.len() + 1) * stride];
+        // 后缀中最大的若干正数（降序）和最小的若干负数（升序）
+        let mut largest: Vec<i64> = Vec::with_capacity(stride);
+        let mut smallest: Vec<i64> = Vec::with_capacity(stride);
+        for (i, &n) in nums.iter().enumerate().rev() {
+            if n > 0 {
+                largest.insert(largest.partition_point(|&x| x >= n), n);
+                largest.truncate(depth);
+            } else if n < 0 {
+                smallest.insert(smallest.partition_point(|&x| x <= n), n);
+                smallest.truncate(depth);
+            }
+            let row = i * stride;
+            for k in 1..stride {
+                pos_top[row + k] = pos_top[row + k - 1] + largest.get(k - 1).copied().unwrap_or(0);
+                neg_top[row + k] = neg_top[row + k - 1] + smallest.get(k - 1).copied().unwrap_or(0);
+            }
+        }
+
+        bounds.depth = depth;
+        bounds.pos_top = pos_top;
+        bounds.neg_top = neg_top;
+        bounds
+    }
+
+    /// 从 `nums[start..]` 中最多再取 `items` 个元素，其和所在的区间
+    fn range(&self, start: usize, items: usize) -> (i64, i64) {
+        if !self.pos_top.is_empty() && items <= self.depth {
+            let at = start * (self.depth + 1) + items;
+            (self.neg_top[at], self.pos_top[at])
+        } else {
+            (self.neg[start], self.pos[start])
+        }
+    }
+
+    /// 当前和为 `sum` 时，再从 `nums[start..]` 中选取元素能否落入 `[low, high]`
+    pub fn reachable(&self, sum: i64, start: usize, low: i64, high: i64) -> bool {
+        self.reachable_within(sum, start, usize::MAX, low, high)
+    }
+
+    /// 同 `reachable`，但最多再选 `items` 个元素
+    pub fn reachable_within(
+        &self,
+        sum: i64,
+        start: usize,
+        items: usize,
+        low: i64,
+        high: i64,
+    ) -> bool {
+        let (neg, pos) = self.range(start, items);
+        sum + neg <= high && low <= sum + pos
+    }
+
+    /// 当前和为 `sum` 时，再从 `nums[start..]` 中选取元素后与 `target` 的最小可能差距
+    pub fn distance(&self, sum: i64, start: usize, target: i64) -> i64 {
+        self.distance_within(sum, start, usize::MAX, target)
+    }
+
+    /// 同 `distance`，但最多再选 `items` 个元素
+    pub fn distance_within(&self, sum: i64, start: usize, items: usize, target: i64) -> i64 {
+        let (neg, pos) = self.range(start, items);
+        let low = sum + neg;
+        let high = sum + pos;
+        if target < low {
+            low - target
+        } else if target > high {
+            target - high
+        } else {
+            0
+        }
+    }
+}
+
+/// 按回溯顺序惰性产生所有和为目标值的组合
+///
+/// 组合按输入顺序的字典序产生，每个解是已选元素在输入中的下标（升序）。
+/// 内部用显式栈代替递归，输入再长也不会栈溢出；调用方可以随时停止迭代。
+/// 设置容差后，和落在 `[target - tolerance, target + tolerance]` 内的组合都算命中；
+/// 限制元素个数后，只产生个数在范围内的组合，并据此剪枝。
+/// 开始迭代前克隆，可以得到设置相同的另一次搜索（例如另一棵子树）；
+/// 先调用 `share_bounds` 再克隆，各次搜索共用同一张边界表。
+#[derive(Clone)]
+pub struct SubsetSumSolutions {
+    values: Vec<i64>,
+    /// 剪枝用的边界表，第一次迭代时按元素个数上限建立
+    bounds: Option<Arc<SuffixBounds>>,
+    target: i64,
+    /// 目标区间的下界和上界
+    low: i64,
+    high: i64,
+    /// 组合允许的最少和最多元素个数
+    min_items: usize,
+    max_items: usize,
+    /// 已选元素的下标，即回溯的显式栈
+    path: Vec<usize>,
+    sum: i64,
+    /// 下一个尝试加入的下标
+    next: usize,
+    /// 子树的起始下标和栈中不能弹出的固定前缀长度（只搜索子树时使用）
+    start: usize,
+    floor: usize,
+    /// 固定前缀本身尚未检查是否命中
+    check_prefix: bool,
+    /// 外部设置的停止标志
+    stop: Option<Arc<AtomicBool>>,
+    /// 共享的搜索预算，设置后同时记录最接近目标的组合
+    control: Option<Arc<SearchControl>>,
+    best: Option<(i64, Vec<usize>)>,
+    /// 已经计入共享搜索控制的覆盖比例
+    reported: f64,
+    steps: u32,
+    done: bool,
+    /// 搜索空间已全部覆盖（不是被中途停下）
+    complete: bool,
+}
+
+impl SubsetSumSolutions {
+    pub fn new<T: HasAmount>(items: &[T], target: Amount) -> Self {
+        let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+        SubsetSumSolutions {
+            values,
+            bounds: None,
+            target: target.units(),
+            low: target.units(),
+            high: target.units(),
+            min_items: 1,
+            max_items: usize::MAX,
+            path: Vec::new(),
+            sum: 0,
+            next: 0,
+            start: 0,
+            floor: 0,
+            check_prefix: false,
+            stop: None,
+            control: None,
+            best: None,
+            reported: 0.0,
+            steps: 0,
+            done: false,
+            complete: false,
+        }
+    }
+
+    /// 只搜索前 `start` 个元素的取舍已经固定的子树，`chosen` 为其中被选中的下标
+    ///
+    /// 所有前缀对应的子树互不相交，合起来正好覆盖整棵搜索树，可以分给多个线程。
+    pub fn subtree(mut self, chosen: &[usize], start: usize) -> Self {
+        self.path = chosen.to_vec();
+        self.sum = chosen.iter().map(|&i| self.values[i]).sum();
+        self.next = start;
+        self.start = start;
+        self.floor = chosen.len();
+        self.check_prefix = !chosen.is_empty();
+        self
+    }
+
+    /// 输入的元素个数
+    pub fn item_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// 组合允许的元素个数范围
+    pub fn sizes(&self) -> RangeInclusive<usize> {
+        self.min_items..=self.max_items
+    }
+
+    /// 共享的搜索控制，未设置时为 `None`
+    pub fn search_control(&self) -> Option<&Arc<SearchControl>> {
+        self.control.as_ref()
+    }
+
+    /// 标志被置位后迭代尽快结束
+    pub fn stop_on(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// 受共享的时间和节点预算约束，结束时把覆盖比例和最接近的组合报告给它
+    pub fn control(mut self, control: Arc<SearchControl>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    fn stopped(&mut self) -> bool {
+        self.steps += 1;
+        if self.steps < CHECK_INTERVAL {
+            return false;
+        }
+        self.steps = 0;
+        self.publish();
+        let budget_spent = self
+            .control
+            .as_ref()
+            .is_some_and(|control| control.tick(u64::from(CHECK_INTERVAL)));
+        budget_spent
+            || self
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    /// 已覆盖的搜索空间比例（相对于本子树）
+    ///
+    /// 按先选后不选的二叉树计：下标 `j` 不在栈中且小于 `next`，
+    /// 说明选中它的那一半子树已经搜完，占 2^-(j+1-start)。
+    pub fn explored(&self) -> f64 {
+        if self.complete {
+            return 1.0;
+        }
+        let mut path = self.path[self.floor..].iter().peekable();
+        let mut explored = 0.0;
+        for j in self.start..self.next.min(self.values.len()) {
+            if path.peek() == Some(&&j) {
+                path.next();
+            } else {
+                explored += 0.5f64.powi((j + 1 - self.start) as i32);
+            }
+        }
+        explored
+    }
+
+    /// 把当前深度和新增的覆盖比例交给共享的搜索控制
+    fn publish(&mut self) {
+        if let Some(control) = &self.control {
+            let weight = 0.5f64.powi(self.start as i32);
+            let explored = self.explored() * weight;
+            control.add_explored(explored - self.reported);
+            control.set_depth(self.path.len());
+            self.reported = explored;
+        }
+    }
+
+    /// 迭代结束，把统计信息和最接近目标的组合交给共享的搜索控制
+    fn finish(&mut self, complete: bool) {
+        self.done = true;
+        self.complete = complete;
+        self.publish();
+        if let Some(control) = &self.control {
+            control.tick(u64::from(self.steps));
+            self.steps = 0;
+            if let Some((distance, indices)) = self.best.take() {
+                control.offer(distance, indices);
+            }
+        }
+    }
+
+    /// 产生一个解，同时计入共享的统计
+    fn emit(&self) -> Option<Vec<usize>> {
+        if let Some(control) = &self.control {
+            control.add_solutions(1);
+        }
+        Some(self.path.clone())
+    }
+
+    /// 允许组合的和与目标值相差不超过 `tolerance`
+    pub fn tolerance(mut self, tolerance: Amount) -> Self {
+        let tolerance = tolerance.units().abs();
+        self.low = self.target - tolerance;
+        self.high = self.target + tolerance;
+        self
+    }
+
+    /// 只接受元素个数在 `items` 范围内的组合
+    ///
+    /// 已经建好的边界表保留，它对任何上限都成立，对不超过建表时上限的个数同样紧。
+    pub fn items(mut self, items: RangeInclusive<usize>) -> Self {
+        self.min_items = (*items.start()).max(1);
+        self.max_items = *items.end();
+        self
+    }
+
+    /// 现在就按元素个数上限建立边界表，之后克隆出的搜索都共用它
+    pub fn share_bounds(mut self) -> Self {
+        self.build_bounds();
+        self
+    }
+
+    fn build_bounds(&mut self) {
+        if self.bounds.is_none() {
+            let bounds = SuffixBounds::with_max_items(&self.values, self.max_items);
+            self.bounds = Some(Arc::new(bounds));
+        }
+    }
+
+    /// 当前组合是否命中：和在目标区间内且元素个数满足要求
+    fn hit(&self) -> bool {
+        self.low <= self.sum
+            && self.sum <= self.high
+            && (self.min_items..=self.max_items).contains(&self.path.len())
+    }
+
+    /// 加入下标 `i` 后是否还可能得到满足条件的组合
+    fn promising(&self, bounds: &SuffixBounds, i: usize) -> bool {
+        let len = self.path.len();
+        len < self.max_items
+            && self.values.len() - i >= self.min_items.saturating_sub(len)
+            && bounds.reachable_within(self.sum, i, self.max_items - len, self.low, self.high)
+    }
+}
+
+impl Iterator for SubsetSumSolutions {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        self.build_bounds();
+        let bounds = self.bounds.clone()?;
+        if self.check_prefix {
+            self.check_prefix = false;
+            if self.hit() {
+                return self.emit();
+            }
+        }
+
+        while !self.done {
+            if self.stopped() {
+                self.finish(false);
+                break;
+            }
+
+            let i = self.next;
+            if i < self.values.len() && self.promising(&bounds, i) {
+                self.path.push(i);
+                self.sum += self.values[i];
+                self.next = i + 1;
+                if self.control.is_some() && self.path.len() >= self.min_items {
+                    let distance = (self.sum - self.target).abs();
+                    if self.best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                        self.best = Some((distance, self.path.clone()));
+                    }
+                }
+                // 命中后下次从这里继续向下搜索：再加上和为 0 的元素同样满足条件
+                if self.hit() {
+                    return self.emit();
+                }
+                continue;
+            }
+
+            // 剩余元素无论怎么选都到不了目标值，回溯到上一层
+            if self.path.len() == self.floor {
+                self.finish(true);
+                break;
+            }
+            if let Some(last) = self.path.pop() {
+                self.sum -= self.values[last];
+                self.next = last + 1;
+            }
+        }
+        None
+    }
+}
+
+/// 使用回溯算法查找第一个可能的组合
+///
+/// 组合按输入顺序的字典序搜索，返回的是第一个和恰好等于 `target` 的非空组合。
+pub fn find_first_combination<T: HasAmount + Clone>(items: &[T], target: Amount) -> Option<Vec<T>> {
+    SubsetSumSolutions::new(items, target)
+        .next()
+        .map(|indices| indices.iter().map(|&i| items[i].clone()).collect())
+}
+
+/// 使用回溯算法查找所有和为 `target` 的组合，`limit` 限制最多返回的组合数
+pub fn find_all_combinations<T: HasAmount + Clone>(
+    items: &[T],
+    target: Amount,
+    limit: Option<usize>,
+) -> Vec<Vec<T>> {
+    SubsetSumSolutions::new(items, target)
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|indices| indices.iter().map(|&i| items[i].clone()).collect())
+        .collect()
+}
+
+/// 查找和最接近 `target` 且元素个数在 `sizes` 范围内的组合，返回下标和差额（组合之和减去目标值）
+///
+/// 用分支限界搜索：剩余元素无论怎么选都不可能比当前最优更接近时剪枝，
+/// 因此返回的一定是全局最接近的组合；差距相同时保留回溯顺序中较早的那个。
+/// 搜索预算用尽时返回目前找到的最接近的组合。
+pub fn find_closest_combination<T: HasAmount>(
+    items: &[T],
+    target: Amount,
+    sizes: RangeInclusive<usize>,
+    control: &SearchControl,
+) -> Option<(Vec<usize>, Amount)> {
+    let values: Vec<i64> = items.iter().map(|n| n.amount().units()).collect();
+    let min_items = (*sizes.start()).max(1);
+    let max_items = *sizes.end();
+    let bounds = SuffixBounds::with_max_items(&values, max_items);
+    let target_units = target.units();
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    let mut path: Vec<usize> = Vec::new();
+    let mut sum = 0;
+    let mut next = 0;
+    let mut steps = 0;
+
+    loop {
+        steps += 1;
+        if steps == CHECK_INTERVAL {
+            steps = 0;
+            control.set_depth(path.len());
+            if control.tick(u64::from(CHECK_INTERVAL)) {
+                break;
+            }
+        }
+
+        let i = next;
+        let len = path.len();
+        let promising = len < max_items
+            && values.len() - i.min(values.len()) >= min_items.saturating_sub(len)
+            && match &best {
+                Some((best_distance, _)) => {
+                    bounds.distance_within(sum, i, max_items - len, target_units) < *best_distance
+                }
+                None => true,
+            };
+        if i < values.len() && promising {
+            path.push(i);
+            sum += values[i];
+            next = i + 1;
+            let distance = (sum - target_units).abs();
+            if path.len() >= min_items && best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                best = Some((distance, path.clone()));
+                if distance == 0 {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        match path.pop() {
+            Some(last) => {
+                sum -= values[last];
+                next = last + 1;
+            }
+            None => break,
+        }
+    }
+
+    best.map(|(_, indices)| {
+        let sum: i64 = indices.iter().map(|&i| values[i]).sum();
+        (indices, Amount::from_units(sum, target.scale()) - target)
+    })
+}
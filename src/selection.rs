@@ -0,0 +1,118 @@
+use crate::record::Record;
+
+/// 行在标记列中的取值
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowFlag {
+    /// 必须出现在组合中
+    Include,
+    /// 不参与匹配
+    Exclude,
+}
+
+impl RowFlag {
+    /// 解析标记列的取值，`include`/`+` 和 `exclude`/`-`（不区分大小写），其余视为未标记
+    pub fn parse(value: &str) -> Option<RowFlag> {
+        match value.to_ascii_lowercase().as_str() {
+            "include" | "+" => Some(RowFlag::Include),
+            "exclude" | "-" => Some(RowFlag::Exclude),
+            _ => None,
+        }
+    }
+}
+
+/// 搜索前固定选中和排除的行（记录下标）
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RowSelection {
+    pub pinned: Vec<usize>,
+    pub excluded: Vec<usize>,
+}
+
+/// 找出 `value` 指定的行：优先按 ID（金额之外的第一列）匹配，
+/// 没有匹配的 ID 且 `value` 是整数时按行号匹配
+fn matching_rows(records: &[Record], value: &str) -> Vec<usize> {
+    let by_id: Vec<usize> = records
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.fields.first().is_some_and(|id| id == value))
+        .map(|(i, _)| i)
+        .collect();
+    if !by_id.is_empty() {
+        return by_id;
+    }
+    match value.parse::<usize>() {
+        Ok(line) => records
+            .iter()
+            .position(|r| r.line == line)
+            .into_iter()
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+impl RowSelection {
+    /// 根据命令行给出的行号或 ID 以及文件中的标记列（`fields` 中的下标）确定固定和排除的行
+    ///
+    /// 指定的值没有对应的行，或者同一行既被固定又被排除时返回错误。
+    pub fn new(
+        records: &[Record],
+        include: &[String],
+        exclude: &[String],
+        flag_field: Option<usize>,
+    ) -> Result<Self, String> {
+        let mut flags: Vec<Option<RowFlag>> = records
+            .iter()
+            .map(|r| {
+                flag_field
+                    .and_then(|f| r.fields.get(f))
+                    .and_then(|value| RowFlag::parse(value))
+            })
+            .collect();
+
+        // 命令行的指定优先于标记列
+        let mut explicit = vec![false; records.len()];
+        for (values, flag, option) in [
+            (include, RowFlag::Include, "--include-rows"),
+            (exclude, RowFlag::Exclude, "--exclude-rows"),
+        ] {
+            for value in values {
+                let rows = matching_rows(records, value);
+                if rows.is_empty() {
+                    return Err(format!("{}: no row matches `{}`", option, value));
+                }
+                for i in rows {
+                    if explicit[i] && flags[i] != Some(flag) {
+                        return Err(format!(
+                            "line {} is both included and excluded",
+                            records[i].line
+                        ));
+                    }
+                    flags[i] = Some(flag);
+                    explicit[i] = true;
+                }
+            }
+        }
+
+        let mut selection = RowSelection::default();
+        for (i, flag) in flags.iter().enumerate() {
+            match flag {
+                Some(RowFlag::Include) => selection.pinned.push(i),
+                Some(RowFlag::Exclude) => selection.excluded.push(i),
+                None => {}
+            }
+        }
+        Ok(selection)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pinned.is_empty() && self.excluded.is_empty()
+    }
+
+    /// 去掉固定和排除的行后剩下的候选行下标
+    pub fn candidates(&self, records: &[Record]) -> Vec<usize> {
+        let mut removed = vec![false; records.len()];
+        for &i in self.pinned.iter().chain(&self.excluded) {
+            removed[i] = true;
+        }
+        (0..records.len()).filter(|&i| !removed[i]).collect()
+    }
+}
@@ -0,0 +1,79 @@
+use find::allocation::allocate;
+use find::amount::Amount;
+use find::control::SearchControl;
+use std::sync::Arc;
+
+fn amounts(units: &[i64]) -> Vec<Amount> {
+    units.iter().map(|&u| Amount::from_units(u, 2)).collect()
+}
+
+/// 简单的线性同余生成器，保证测试可复现
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, range: i64) -> i64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % (2 * range as u64 + 1)) as i64 - range
+    }
+}
+
+#[test]
+fn disjoint_allocation_satisfies_as_many_targets_as_possible() {
+    let mut rng = Lcg(73);
+    for _ in 0..200 {
+        let len = (rng.next(2) + 7) as usize;
+        let nums: Vec<i64> = (0..len).map(|_| rng.next(20)).collect();
+        let targets: Vec<i64> = (0..3).map(|_| rng.next(25)).collect();
+
+        // 穷举每行分给哪个目标（或不分），统计最多能满足几个目标
+        let best = (0..4usize.pow(len as u32))
+            .map(|code| {
+                let mut sums = [0i64; 3];
+                let mut counts = [0usize; 3];
+                let mut code = code;
+                for &n in &nums {
+                    if code % 4 < 3 {
+                        sums[code % 4] += n;
+                        counts[code % 4] += 1;
+                    }
+                    code /= 4;
+                }
+                (0..3)
+                    .filter(|&k| counts[k] > 0 && sums[k] == targets[k])
+                    .count()
+            })
+            .max()
+            .unwrap();
+
+        let goals: Vec<(Amount, Amount)> = targets
+            .iter()
+            .map(|&t| (Amount::from_units(t, 2), Amount::zero(2)))
+            .collect();
+        let allocation = allocate(
+            &amounts(&nums),
+            &goals,
+            1..=usize::MAX,
+            &Arc::new(SearchControl::new()),
+        );
+        assert_eq!(
+            allocation.satisfied(),
+            best,
+            "nums = {:?}, targets = {:?}",
+            nums,
+            targets
+        );
+
+        let mut used = vec![0; len];
+        for (k, assignment) in allocation.assignments.iter().enumerate() {
+            if let Some(indices) = assignment {
+                assert_eq!(indices.iter().map(|&i| nums[i]).sum::<i64>(), targets[k]);
+                indices.iter().for_each(|&i| used[i] += 1);
+            }
+        }
+        allocation.unallocated.iter().for_each(|&i| used[i] += 1);
+        assert!(used.iter().all(|&u| u == 1));
+    }
+}
@@ -0,0 +1,67 @@
+use find::amount::{Amount, ParseAmountError, MAX_TOTAL};
+
+#[test]
+fn parse_accepts_signs_and_missing_digits_on_one_side() {
+    let units = |s: &str, scale: u32| Amount::parse(s, scale).map(|a| a.units());
+    assert_eq!(units("-0.05", 2), Ok(-5));
+    assert_eq!(units(".5", 2), Ok(50));
+    assert_eq!(units("5.", 2), Ok(500));
+    assert_eq!(units("+3", 2), Ok(300));
+    assert_eq!(units(" 12 ", 0), Ok(12));
+    // 多余的小数位为 0 时接受，否则报错而不是舍入
+    assert_eq!(units("1.230", 2), Ok(123));
+    assert_eq!(units("1.235", 2), Err(ParseAmountError::TooPrecise));
+    assert_eq!(units("0.5", 0), Err(ParseAmountError::TooPrecise));
+
+    for invalid in [
+        "", " ", "-", "+", ".", "-.", "1.2.3", "1,000", "--1", "1e3", "abc",
+    ] {
+        assert_eq!(
+            Amount::parse(invalid, 2),
+            Err(ParseAmountError::Invalid),
+            "{:?}",
+            invalid
+        );
+    }
+
+    let max = Amount::from_units(MAX_TOTAL, 2);
+    assert_eq!(Amount::parse(&max.to_string(), 2), Ok(max));
+    assert_eq!(Amount::parse(&(-max).to_string(), 2), Ok(-max));
+    let above = format!("{}", i128::from(MAX_TOTAL) + 1);
+    assert_eq!(Amount::parse(&above, 0), Err(ParseAmountError::Overflow));
+    assert_eq!(
+        Amount::parse("99999999999999999999", 2),
+        Err(ParseAmountError::Overflow)
+    );
+}
+
+#[test]
+fn display_pads_the_fraction_and_keeps_the_sign() {
+    let shown = |units: i64, scale: u32| Amount::from_units(units, scale).to_string();
+    assert_eq!(shown(-5, 2), "-0.05");
+    assert_eq!(shown(50, 2), "0.50");
+    assert_eq!(shown(0, 2), "0.00");
+    assert_eq!(shown(-123456, 3), "-123.456");
+    assert_eq!(shown(7, 0), "7");
+    assert_eq!(shown(i64::MIN, 2), "-92233720368547758.08");
+
+    // 显示的结果可以原样解析回来
+    for (units, scale) in [(-5, 2), (1, 9), (-1_000_000, 4), (42, 0)] {
+        let amount = Amount::from_units(units, scale);
+        assert_eq!(Amount::parse(&amount.to_string(), scale), Ok(amount));
+    }
+}
+
+#[test]
+fn percent_rounds_toward_zero_and_keeps_the_scale() {
+    let pct = |s: &str| Amount::parse(s, 4).unwrap();
+    let amount = Amount::from_units(12345, 2);
+    assert_eq!(amount.percent(pct("10")), Amount::from_units(1234, 2));
+    assert_eq!(amount.percent(pct("0.5")), Amount::from_units(61, 2));
+    assert_eq!((-amount).percent(pct("10")), Amount::from_units(-1234, 2));
+    assert_eq!(amount.percent(pct("0")), Amount::zero(2));
+    assert_eq!(amount.percent(pct("100")), amount);
+    // 结果不超过 MAX_TOTAL
+    let max = Amount::from_units(MAX_TOTAL, 2);
+    assert_eq!(max.percent(pct("1000")), max);
+}
@@ -0,0 +1,134 @@
+use find::amount::Amount;
+use find::control::{SearchControl, StopReason};
+use find::search::SubsetSumSolutions;
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 金额都是偶数分、目标是奇数分，剪枝无从下手，搜索必然用尽预算
+fn hard_amounts(len: usize) -> Vec<i64> {
+    (0..len as i64).map(|i| 2 * (1000 + 37 * i)).collect()
+}
+
+#[test]
+fn tick_stops_at_the_first_exhausted_budget() {
+    let unlimited = SearchControl::new();
+    assert!(!unlimited.tick(u64::MAX / 2));
+    assert_eq!(unlimited.stop_reason(), None);
+
+    let nodes = SearchControl::new().max_nodes(3000);
+    assert!(!nodes.tick(1024));
+    assert!(!nodes.tick(1024));
+    assert!(nodes.tick(1024));
+    assert_eq!(nodes.nodes(), 3072);
+    assert_eq!(nodes.stop_reason(), Some(StopReason::NodeLimit));
+    // 停下之后一直保持停止
+    assert!(nodes.tick(1));
+
+    let time = SearchControl::new().timeout(Duration::ZERO);
+    assert!(time.tick(1));
+    assert_eq!(time.stop_reason(), Some(StopReason::Timeout));
+
+    // 只保留第一次的原因
+    let interrupted = SearchControl::new().max_nodes(10);
+    interrupted.stop(StopReason::Interrupted);
+    assert!(interrupted.tick(100));
+    assert_eq!(interrupted.stop_reason(), Some(StopReason::Interrupted));
+}
+
+#[test]
+fn unknown_coverage_is_not_accumulated() {
+    let control = SearchControl::new();
+    control.add_explored(0.5);
+    control.set_explored_unknown();
+    control.add_explored(0.25);
+    assert!(!control.explored_known());
+    assert_eq!(control.explored(), 0.5);
+
+    // 下一次搜索重新估计
+    control.reset();
+    control.add_explored(0.25);
+    assert!(control.explored_known());
+    assert_eq!(control.explored(), 0.25);
+}
+
+#[test]
+fn exhausted_budget_keeps_the_closest_combination_so_far() {
+    let values: Vec<Amount> = hard_amounts(40)
+        .iter()
+        .map(|&u| Amount::from_units(u, 2))
+        .collect();
+    let control = Arc::new(SearchControl::new().max_nodes(5000));
+    let found: Vec<Vec<usize>> = SubsetSumSolutions::new(&values, Amount::from_units(40001, 2))
+        .control(control.clone())
+        .collect();
+
+    assert!(found.is_empty());
+    assert_eq!(control.stop_reason(), Some(StopReason::NodeLimit));
+    assert!(control.nodes() < 10000);
+    let (distance, indices) = control.best().unwrap();
+    let sum: i64 = indices.iter().map(|&i| values[i].units()).sum();
+    assert_eq!(distance, (sum - 40001).abs());
+    assert!(control.explored() > 0.0 && control.explored() < 1.0);
+}
+
+#[test]
+fn budgets_exit_with_code_three_and_report_partial_results() {
+    let dir = std::env::temp_dir().join(format!("find-control-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.txt");
+    let content: Vec<String> = hard_amounts(40)
+        .iter()
+        .map(|&u| Amount::from_units(u, 2).to_string())
+        .collect();
+    fs::write(&input, content.join("\n")).unwrap();
+
+    for budget in [["--max-nodes", "20000"], ["--timeout", "0.2"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_find"))
+            .arg(&input)
+            .args(["-t", "400.01", "--algorithm", "backtrack", "--quiet"])
+            .args(budget)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(3), "{}", stderr);
+        assert!(stdout.contains("sums up to 400.01: None"), "{}", stdout);
+        assert!(
+            stdout.contains("Closest combination found so far to 400.01"),
+            "{}",
+            stdout
+        );
+        let reason = match budget[0] {
+            "--max-nodes" => "Search incomplete: node limit of 20000 reached",
+            _ => "Search incomplete: time limit of 0.2 s reached",
+        };
+        assert!(stderr.contains(reason), "{}", stderr);
+        // 目前最接近的组合不能写得像满足了目标
+        let result = fs::read_to_string(dir.join("result.csv")).unwrap();
+        assert!(
+            result.starts_with("line,amount (closest),row\n"),
+            "{}",
+            result
+        );
+    }
+
+    // --all 被预算打断时同样只给出标明的最接近组合
+    let output = Command::new(env!("CARGO_BIN_EXE_find"))
+        .arg(&input)
+        .args(["-t", "400.01", "--all", "--max-nodes", "20000", "--quiet"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Found 0 combination(s)"), "{}", stdout);
+    let result = fs::read_to_string(dir.join("result.csv")).unwrap();
+    assert!(
+        result.starts_with("line,amount (closest),row\n"),
+        "{}",
+        result
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
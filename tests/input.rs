@@ -0,0 +1,277 @@
+use find::amount::Amount;
+use find::input::{parse_records, read_records, Column, Format, Header, InputOptions};
+use find::locale::NumberFormat;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn options(format: Format, column: Option<Column>) -> InputOptions {
+    InputOptions {
+        scale: 2,
+        format,
+        header: Header::Auto,
+        number: NumberFormat::default(),
+        column,
+        sheet: None,
+        flag_column: None,
+    }
+}
+
+#[test]
+fn delimited_input_selects_column_and_keeps_context() {
+    let csv = Format::Delimited {
+        delimiter: b',',
+        quote: Some(b'"'),
+    };
+    let content = "date,desc,amount\n2024-01-02,\"Office, supplies\",12.50\n\
+                   2024-01-03,\"two\nlines\",-7.5\n2024-01-04,bad,n/a\n";
+
+    // 未指定列时取表头为 amount 的列
+    let table = parse_records(content, &options(csv, None)).unwrap();
+    assert_eq!(table.column, 2);
+    let amounts: Vec<Amount> = table.records.iter().map(|r| r.amount).collect();
+    assert_eq!(
+        amounts,
+        [Amount::from_units(1250, 2), Amount::from_units(-750, 2)]
+    );
+    assert_eq!(table.records[0].fields, ["2024-01-02", "Office, supplies"]);
+    assert_eq!(table.records[1].line, 3);
+    assert_eq!(table.records[1].text, "2024-01-03,\"two\nlines\",-7.5");
+    let rejects: Vec<(usize, &str)> = table
+        .rejects
+        .iter()
+        .map(|r| (r.line, r.reason.as_str()))
+        .collect();
+    assert_eq!(rejects, [(5, "invalid amount `n/a`")]);
+
+    let by_name = parse_records(content, &options(csv, Some("Amount".parse().unwrap())));
+    assert_eq!(by_name.unwrap().records, table.records);
+    let by_index = parse_records(content, &options(csv, Some("3".parse().unwrap())));
+    assert_eq!(by_index.unwrap().records, table.records);
+    assert!(parse_records(content, &options(csv, Some("total".parse().unwrap()))).is_err());
+    // 列字母只用于工作表
+    let fee = parse_records(content, &options(csv, Some("fee".parse().unwrap())));
+    assert_eq!(fee.unwrap_err(), "column `fee` not found in the header");
+    // 金额之和超出范围时拒绝整个文件，不会在求和时溢出
+    let large = InputOptions {
+        scale: 9,
+        ..options(Format::Whitespace, None)
+    };
+    assert!(parse_records("2000000000\n", &large).is_ok());
+    assert!(parse_records("2000000000\n-2000000000\n", &large).is_err());
+
+    // 空白分隔的文本仍按第一列读取，空行不算无法读取的行
+    let table = parse_records("n\n1.5 a b\nx\n\n-2\n", &options(Format::Whitespace, None)).unwrap();
+    let lines: Vec<usize> = table.records.iter().map(|r| r.line).collect();
+    assert_eq!(lines, [2, 5]);
+    let lines: Vec<usize> = table.rejects.iter().map(|r| r.line).collect();
+    assert_eq!(lines, [3]);
+    assert_eq!(table.records[0].fields, ["a", "b"]);
+}
+
+#[test]
+fn header_rows_are_detected_or_configured() {
+    let lines = |content: &str, header: Header| -> Vec<usize> {
+        let options = InputOptions {
+            header,
+            ..options(Format::Whitespace, None)
+        };
+        let table = parse_records(content, &options).unwrap();
+        table.records.iter().map(|r| r.line).collect()
+    };
+
+    // 第一行是数字时不是表头
+    assert_eq!(lines("1.5\n2\n", Header::Auto), [1, 2]);
+    assert_eq!(lines("amount\n1.5\n2\n", Header::Auto), [2, 3]);
+    assert_eq!(lines("1.5\n2\n", Header::Rows(0)), [1, 2]);
+    assert_eq!(lines("1.5\n2\n3\n", Header::Rows(2)), [3]);
+
+    // 列名取最后一行表头
+    let options = InputOptions {
+        header: Header::Rows(2),
+        ..options(Format::Whitespace, Some("net".parse().unwrap()))
+    };
+    let table = parse_records("report 2024\nid net flag\nA 3 +\n", &options).unwrap();
+    assert_eq!(table.column, 1);
+    assert_eq!(table.amount_label(), "net");
+    assert_eq!(table.field_index(&"flag".parse().unwrap()), Ok(1));
+    assert_eq!(table.field_index(&Column::Index(0)), Ok(0));
+    assert!(table.field_index(&Column::Index(1)).is_err());
+}
+
+#[test]
+fn number_formats_accept_locale_and_accounting_notation() {
+    let en = NumberFormat::default();
+    let de = NumberFormat::for_locale("de_DE").unwrap();
+    let fr = NumberFormat::for_locale("fr-FR").unwrap();
+    let ch = NumberFormat::for_locale("de-CH").unwrap();
+    let cases = [
+        (en, "1,234.56", Some(123456)),
+        (en, "-1,234,567.8", Some(-123456780)),
+        (en, "(500.00)", Some(-50000)),
+        (en, "500.00-", Some(-50000)),
+        (en, "$1,234.56", Some(123456)),
+        (en, "-$12", Some(-1200)),
+        (en, "($12.50)", Some(-1250)),
+        (en, "¥ 3,000", Some(300000)),
+        (en, "12.00 EUR", Some(1200)),
+        (en, "HK$5", Some(500)),
+        (en, "１，２３４．５０", Some(123450)),
+        (en, "－１２", Some(-1200)),
+        (en, "1,00,000.00", Some(10000000)),
+        (en, "1,5", None),
+        (en, "1.234,56", None),
+        (en, "(-5)", None),
+        (en, "n/a", None),
+        (en, "INV100", None),
+        (en, "ABC12.50", None),
+        (en, "12.50 XYZ", None),
+        (en, "100ABC", None),
+        (en, "USD", None),
+        (en, "US$", None),
+        (en, "USD100", Some(10000)),
+        (en, "100 RMB", Some(10000)),
+        (de, "1.234,56", Some(123456)),
+        (de, "-0,5", Some(-50)),
+        (de, "1,234.56", None),
+        (fr, "1 234,56 €", Some(123456)),
+        (fr, "1\u{a0}234,56", Some(123456)),
+        (ch, "1'234.56", Some(123456)),
+        (en.thousands_sep(None), "1,234", None),
+        (en.decimal_sep(','), "1.234,5", Some(123450)),
+    ];
+    for (format, text, expected) in cases {
+        let parsed = format.parse(text, 2).ok().map(|a| a.units());
+        assert_eq!(parsed, expected, "{:?} with {:?}", text, format);
+    }
+    assert!(NumberFormat::for_locale("xx").is_err());
+
+    // 空白分隔时被拆开的金额不能只读前半部分
+    assert!(fr.continues("1", "234,56"));
+    assert!(fr.continues("12", "345"));
+    assert!(en.continues("500.00", "-"));
+    assert!(!fr.continues("1,50", "234"));
+    assert!(!fr.continues("1", "2024-01-02"));
+    assert!(!en.continues("1", "234.56"));
+    let options = InputOptions {
+        number: fr,
+        ..options(Format::Whitespace, None)
+    };
+    let table = parse_records("1 234,56\n500,00 -\n7,00 loyer\n", &options).unwrap();
+    let amounts: Vec<i64> = table.records.iter().map(|r| r.amount.units()).collect();
+    assert_eq!(amounts, [700]);
+    let rejects: Vec<(usize, &str)> = table
+        .rejects
+        .iter()
+        .map(|r| (r.line, r.reason.as_str()))
+        .collect();
+    assert_eq!(
+        rejects,
+        [
+            (
+                1,
+                "amount `1` continues in the next field `234,56`, use --delimiter"
+            ),
+            (
+                2,
+                "amount `500,00` continues in the next field `-`, use --delimiter"
+            ),
+        ]
+    );
+    assert!(en.thousands_sep(Some('.')).validate().is_err());
+}
+
+#[test]
+fn spreadsheet_rows_keep_sheet_positions() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/ledger.xlsx");
+    assert_eq!(Format::detect(&path), Format::Spreadsheet);
+
+    // 数据从 B2 开始：标题一行、表头一行，金额在 D 列
+    let options = InputOptions {
+        header: Header::Rows(2),
+        sheet: Some(String::from("Ledger")),
+        ..options(Format::Spreadsheet, Some("D".parse().unwrap()))
+    };
+    let table = read_records(&path, &options).unwrap();
+    assert_eq!(table.column, 3);
+    assert_eq!(table.amount_label(), "amount");
+    let rows: Vec<(usize, i64)> = table
+        .records
+        .iter()
+        .map(|r| (r.line, r.amount.units()))
+        .collect();
+    // 0.1 + 0.2 这样的浮点误差按 15 位有效数字取整
+    assert_eq!(rows, [(4, 1250), (5, -2000), (6, 30)]);
+    // A 列为空，不计入 fields
+    assert_eq!(table.first_column, 1);
+    assert_eq!(table.records[0].fields, ["2024-01-02", "Office, supplies"]);
+    assert_eq!(table.field_index(&"B".parse().unwrap()), Ok(0));
+    assert!(table.field_index(&"A".parse().unwrap()).is_err());
+    assert_eq!(
+        table.records[0].text,
+        "2024-01-02,\"Office, supplies\",12.5"
+    );
+    assert_eq!(table.rejects.len(), 1);
+
+    let by_number = InputOptions {
+        sheet: Some(String::from("2")),
+        ..options.clone()
+    };
+    assert_eq!(read_records(&path, &by_number).unwrap(), table);
+    // 数值单元格不按语言区域解析
+    let german = InputOptions {
+        number: NumberFormat::for_locale("de-DE").unwrap(),
+        ..options.clone()
+    };
+    assert_eq!(read_records(&path, &german).unwrap(), table);
+    let missing = InputOptions {
+        sheet: Some(String::from("Missing")),
+        ..options
+    };
+    assert!(read_records(&path, &missing).is_err());
+}
+
+#[test]
+fn rejected_lines_are_written_and_stop_a_strict_run() {
+    let dir = std::env::temp_dir().join(format!("find-rejects-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.txt");
+    fs::write(&input, "amount\n1.00\nn/a\n2.00\n\n3,5,0\n").unwrap();
+    let rejects = dir.join("rejects.csv");
+    let run = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_find"))
+            .arg(&input)
+            .args(["-t", "3", "--quiet"])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Skipped 2 line(s)"), "{}", stderr);
+    let path = input.to_str().unwrap();
+    assert_eq!(
+        fs::read_to_string(&rejects).unwrap(),
+        format!(
+            "file,line,reason,row\n{0},3,invalid amount `n/a`,n/a\n\
+             {0},6,\"invalid amount `3,5,0`\",\"3,5,0\"\n",
+            path
+        )
+    );
+
+    // --strict 时不再继续搜索
+    let output = run(&["--strict"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("2 line(s) could not be read, stopping because of --strict"));
+    assert!(String::from_utf8_lossy(&output.stdout).is_empty());
+
+    // 修好输入后，上次留下的 rejects.csv 被删除
+    fs::write(&input, "amount\n1.00\n2.00\n").unwrap();
+    assert!(run(&["--strict"]).status.success());
+    assert!(!rejects.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
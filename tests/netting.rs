@@ -0,0 +1,64 @@
+use find::amount::Amount;
+use find::control::SearchControl;
+use find::netting::net;
+use std::sync::Arc;
+
+fn amounts(units: &[i64]) -> Vec<Amount> {
+    units.iter().map(|&u| Amount::from_units(u, 2)).collect()
+}
+
+/// 简单的线性同余生成器，保证测试可复现
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, range: i64) -> i64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % (2 * range as u64 + 1)) as i64 - range
+    }
+}
+
+#[test]
+fn netting_groups_sum_to_zero_smallest_first() {
+    let mut rng = Lcg(97);
+    for _ in 0..100 {
+        let nums: Vec<i64> = (0..14).map(|_| rng.next(20)).filter(|&n| n != 0).collect();
+        let result = net(
+            &amounts(&nums),
+            Amount::zero(2),
+            3,
+            &Arc::new(SearchControl::new()),
+        );
+
+        let mut used = vec![0; nums.len()];
+        for group in &result.groups {
+            assert_eq!(group.iter().map(|&i| nums[i]).sum::<i64>(), 0, "{:?}", nums);
+            group.iter().for_each(|&i| used[i] += 1);
+        }
+        result.residue.iter().for_each(|&i| used[i] += 1);
+        assert!(used.iter().all(|&u| u == 1));
+        assert!(result.groups.windows(2).all(|w| w[0].len() <= w[1].len()));
+
+        // 残余中不应再有和为 0 的两行或三行
+        let residue: Vec<i64> = result.residue.iter().map(|&i| nums[i]).collect();
+        for (a, &x) in residue.iter().enumerate() {
+            for (b, &y) in residue.iter().enumerate().skip(a + 1) {
+                assert_ne!(x + y, 0, "{:?}", nums);
+                assert!(residue[b + 1..].iter().all(|&z| x + y + z != 0));
+            }
+        }
+    }
+
+    // 金额为 0（或在容差以内）的行单独成组，排在两行的组之前
+    let nums = [300, 0, -300, 700, 1, 5];
+    let result = net(
+        &amounts(&nums),
+        Amount::from_units(1, 2),
+        3,
+        &Arc::new(SearchControl::new()),
+    );
+    assert_eq!(result.groups, [vec![1], vec![4], vec![0, 2]]);
+    assert_eq!(result.residue, [3, 5]);
+}
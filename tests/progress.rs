@@ -0,0 +1,78 @@
+use find::control::SearchControl;
+use find::progress::{format_progress, ProgressFormat};
+
+fn control() -> SearchControl {
+    let control = SearchControl::new();
+    control.tick(2048);
+    control.set_depth(5);
+    control.add_solutions(2);
+    control.add_explored(0.25);
+    control
+}
+
+#[test]
+fn json_progress_has_fixed_keys_in_order() {
+    let line = format_progress(&control(), ProgressFormat::Json);
+    let body = line
+        .strip_prefix('{')
+        .and_then(|l| l.strip_suffix('}'))
+        .unwrap_or_else(|| panic!("not a JSON object: {}", line));
+    let fields: Vec<(&str, &str)> = body
+        .split(',')
+        .map(|field| field.split_once(':').unwrap())
+        .collect();
+    let keys: Vec<&str> = fields.iter().map(|(key, _)| *key).collect();
+    assert_eq!(
+        keys,
+        [
+            "\"elapsed\"",
+            "\"nodes\"",
+            "\"depth\"",
+            "\"solutions\"",
+            "\"explored\""
+        ]
+    );
+
+    // 已用时间以秒计，保留三位小数；覆盖比例是 0 到 1 之间的小数
+    let (_, elapsed) = fields[0];
+    assert!(elapsed.parse::<f64>().is_ok_and(|e| e >= 0.0), "{}", line);
+    assert_eq!(elapsed.split_once('.').map(|(_, f)| f.len()), Some(3));
+    assert_eq!(
+        &fields[1..],
+        [
+            ("\"nodes\"", "2048"),
+            ("\"depth\"", "5"),
+            ("\"solutions\"", "2"),
+            ("\"explored\"", "0.250000")
+        ]
+    );
+}
+
+#[test]
+fn text_progress_reads_as_one_line() {
+    let line = format_progress(&control(), ProgressFormat::Text);
+    assert!(line.starts_with('['), "{}", line);
+    assert!(
+        line.ends_with("s] nodes 2048, depth 5, solutions 2, explored 25.00%"),
+        "{}",
+        line
+    );
+}
+
+#[test]
+fn unknown_coverage_is_null_or_left_out() {
+    let control = control();
+    control.set_explored_unknown();
+    let json = format_progress(&control, ProgressFormat::Json);
+    assert!(
+        json.ends_with(",\"solutions\":2,\"explored\":null}"),
+        "{}",
+        json
+    );
+    let text = format_progress(&control, ProgressFormat::Text);
+    assert!(
+        text.ends_with("s] nodes 2048, depth 5, solutions 2"),
+        "{}",
+        text
+    );
+}
@@ -0,0 +1,82 @@
+use find::amount::Amount;
+use find::control::SearchControl;
+use find::reconcile::{reconcile, MatchKind};
+use std::sync::Arc;
+
+fn amounts(units: &[i64]) -> Vec<Amount> {
+    units.iter().map(|&u| Amount::from_units(u, 2)).collect()
+}
+
+/// 简单的线性同余生成器，保证测试可复现
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, range: i64) -> i64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % (2 * range as u64 + 1)) as i64 - range
+    }
+}
+
+#[test]
+fn reconciliation_groups_balance_and_use_rows_once() {
+    let mut rng = Lcg(89);
+    for _ in 0..100 {
+        let left: Vec<i64> = (0..8).map(|_| rng.next(30).abs() + 1).collect();
+        let right: Vec<i64> = (0..9).map(|_| rng.next(30).abs() + 1).collect();
+        let result = reconcile(
+            &amounts(&left),
+            &amounts(&right),
+            Amount::zero(2),
+            3,
+            &Arc::new(SearchControl::new()),
+        );
+
+        let mut left_used = vec![0; left.len()];
+        let mut right_used = vec![0; right.len()];
+        for group in &result.groups {
+            let a: i64 = group.left.iter().map(|&i| left[i]).sum();
+            let b: i64 = group.right.iter().map(|&j| right[j]).sum();
+            assert_eq!(a, b, "left = {:?}, right = {:?}", left, right);
+            let shape = (group.left.len(), group.right.len());
+            match group.kind {
+                MatchKind::OneToOne => assert_eq!(shape, (1, 1)),
+                MatchKind::OneToMany => assert!(shape.0 == 1 || shape.1 == 1),
+                MatchKind::ManyToMany => assert!(shape.0 >= 2 && shape.1 >= 2),
+            }
+            group.left.iter().for_each(|&i| left_used[i] += 1);
+            group.right.iter().for_each(|&j| right_used[j] += 1);
+        }
+        result
+            .unmatched_left
+            .iter()
+            .for_each(|&i| left_used[i] += 1);
+        result
+            .unmatched_right
+            .iter()
+            .for_each(|&j| right_used[j] += 1);
+        assert!(left_used.iter().chain(&right_used).all(|&u| u == 1));
+
+        // 剩下的行中不应再有相等的一对
+        for &i in &result.unmatched_left {
+            assert!(result.unmatched_right.iter().all(|&j| left[i] != right[j]));
+        }
+    }
+
+    // 有容差时精确相等的一对优先，不能被靠前的近似行抢走
+    let result = reconcile(
+        &amounts(&[10001, 10000]),
+        &amounts(&[10000]),
+        Amount::from_units(1, 2),
+        3,
+        &Arc::new(SearchControl::new()),
+    );
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(
+        (&result.groups[0].left, &result.groups[0].right),
+        (&vec![1], &vec![0])
+    );
+    assert_eq!(result.unmatched_left, vec![0]);
+}
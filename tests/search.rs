@@ -0,0 +1,436 @@
+use find::amount::Amount;
+use find::control::SearchControl;
+use find::dp::SubsetSumTable;
+use find::mitm::{closest_in_the_middle, meet_in_the_middle};
+use find::parallel::{fewest_first, parallel_solutions};
+use find::search::{
+    find_all_combinations, find_closest_combination, find_first_combination, SubsetSumSolutions,
+};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+fn amounts(units: &[i64]) -> Vec<Amount> {
+    units.iter().map(|&u| Amount::from_units(u, 2)).collect()
+}
+
+/// 穷举所有非空子集，按回溯的字典序返回第一个和为 `target` 的组合
+fn brute_force_first(nums: &[i64], target: i64) -> Option<Vec<i64>> {
+    fn visit(nums: &[i64], target: i64, start: usize, path: &mut Vec<i64>) -> bool {
+        for i in start..nums.len() {
+            path.push(nums[i]);
+            if path.iter().sum::<i64>() == target || visit(nums, target, i + 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    visit(nums, target, 0, &mut path).then_some(path)
+}
+
+/// 穷举所有非空子集，按回溯的字典序返回所有和为 `target` 的组合
+fn brute_force_all(nums: &[i64], target: i64) -> Vec<Vec<i64>> {
+    fn visit(
+        nums: &[i64],
+        target: i64,
+        start: usize,
+        path: &mut Vec<i64>,
+        out: &mut Vec<Vec<i64>>,
+    ) {
+        for i in start..nums.len() {
+            path.push(nums[i]);
+            if path.iter().sum::<i64>() == target {
+                out.push(path.clone());
+            }
+            visit(nums, target, i + 1, path, out);
+            path.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    visit(nums, target, 0, &mut Vec::new(), &mut out);
+    out
+}
+
+/// 所有非空子集（升序下标），按位掩码从小到大排列
+fn subsets(len: usize) -> impl Iterator<Item = Vec<usize>> {
+    (1u32..1 << len).map(move |mask| (0..len).filter(|i| mask & (1 << i) != 0).collect())
+}
+
+/// 简单的线性同余生成器，保证测试可复现
+struct Lcg(u64);
+
+impl Lcg {
+    fn step(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn next(&mut self, range: i64) -> i64 {
+        (self.step() % (2 * range as u64 + 1)) as i64 - range
+    }
+}
+
+/// `count` 组可复现的随机金额，个数在 `len` 之内，每个在 `-range..=range` 之内；
+/// 每组附带自己的生成器，供测试继续抽取目标等参数
+fn random_cases(
+    seed: u64,
+    count: usize,
+    len: RangeInclusive<usize>,
+    range: i64,
+) -> impl Iterator<Item = (Vec<i64>, Lcg)> {
+    let mut rng = Lcg(seed);
+    (0..count).map(move |_| {
+        let n = len.start() + rng.step() as usize % (len.end() - len.start() + 1);
+        let nums = (0..n).map(|_| rng.next(range)).collect();
+        (nums, Lcg(rng.step()))
+    })
+}
+
+#[test]
+fn finds_combination_mixing_debits_and_credits() {
+    // 旧的剪枝会在 50 超过 |20| 时直接放弃
+    let nums = amounts(&[50, -30, 7]);
+    let result = find_first_combination(&nums, Amount::from_units(20, 2));
+    assert_eq!(result, Some(amounts(&[50, -30])));
+}
+
+#[test]
+fn finds_positive_target_from_mostly_negative_input() {
+    let nums = amounts(&[-10, -20, -50, 80, -72]);
+    let result = find_first_combination(&nums, Amount::from_units(30, 2));
+    assert_eq!(result, Some(amounts(&[-50, 80])));
+}
+
+#[test]
+fn zero_target_requires_non_empty_combination() {
+    let nums = amounts(&[10, 20]);
+    assert_eq!(find_first_combination(&nums, Amount::zero(2)), None);
+
+    let nums = amounts(&[10, 25, -10]);
+    let result = find_first_combination(&nums, Amount::zero(2));
+    assert_eq!(result, Some(amounts(&[10, -10])));
+}
+
+#[test]
+fn matches_brute_force_on_random_mixed_sign_inputs() {
+    for (nums, mut rng) in random_cases(42, 500, 1..=13, 50) {
+        let target = rng.next(80);
+
+        let expected = brute_force_first(&nums, target).map(|path| amounts(&path));
+        let actual = find_first_combination(&amounts(&nums), Amount::from_units(target, 2));
+        assert_eq!(actual, expected, "nums = {:?}, target = {}", nums, target);
+    }
+}
+
+#[test]
+fn all_mode_matches_brute_force_and_respects_limit() {
+    for (nums, mut rng) in random_cases(7, 200, 3..=11, 20) {
+        let target = rng.next(30);
+
+        let expected: Vec<Vec<Amount>> = brute_force_all(&nums, target)
+            .iter()
+            .map(|path| amounts(path))
+            .collect();
+        let target = Amount::from_units(target, 2);
+        let actual = find_all_combinations(&amounts(&nums), target, None);
+        assert_eq!(actual, expected, "nums = {:?}", nums);
+
+        let limited = find_all_combinations(&amounts(&nums), target, Some(3));
+        assert_eq!(limited, expected[..expected.len().min(3)]);
+    }
+}
+
+#[test]
+fn iterator_yields_indices_lazily() {
+    let nums = amounts(&[100, 200, 300, -100, 400]);
+    let mut solutions = SubsetSumSolutions::new(&nums, Amount::from_units(300, 2));
+    assert_eq!(solutions.next(), Some(vec![0, 1]));
+    assert_eq!(solutions.next(), Some(vec![0, 2, 3]));
+    assert_eq!(solutions.next(), Some(vec![2]));
+    assert_eq!(solutions.next(), Some(vec![3, 4]));
+    assert_eq!(solutions.next(), None);
+    assert_eq!(solutions.next(), None);
+}
+
+#[test]
+fn deep_search_does_not_overflow_the_stack() {
+    let nums = amounts(&vec![1; 200_000]);
+    let solution = find_first_combination(&nums, Amount::from_units(200_000, 2));
+    assert_eq!(solution.map(|s| s.len()), Some(200_000));
+}
+
+#[test]
+fn tolerance_accepts_sums_inside_the_window() {
+    for (nums, mut rng) in random_cases(11, 200, 3..=11, 20) {
+        let len = nums.len();
+        let target = rng.next(30);
+        let tolerance = rng.next(3).abs();
+
+        let expected: Vec<Vec<usize>> = subsets(len)
+            .filter(|c| (c.iter().map(|&i| nums[i]).sum::<i64>() - target).abs() <= tolerance)
+            .collect();
+        let mut actual: Vec<Vec<usize>> =
+            SubsetSumSolutions::new(&amounts(&nums), Amount::from_units(target, 2))
+                .tolerance(Amount::from_units(tolerance, 2))
+                .collect();
+        actual.sort_by_key(|c| c.iter().fold(0u32, |mask, &i| mask | 1 << i));
+        assert_eq!(actual, expected, "nums = {:?}, target = {}", nums, target);
+    }
+}
+
+#[test]
+fn closest_combination_has_minimal_distance() {
+    for (nums, mut rng) in random_cases(23, 200, 3..=11, 40) {
+        let len = nums.len();
+        let target = rng.next(200);
+
+        let best = subsets(len)
+            .map(|c| (c.iter().map(|&i| nums[i]).sum::<i64>() - target).abs())
+            .min()
+            .unwrap();
+        let (indices, diff) = find_closest_combination(
+            &amounts(&nums),
+            Amount::from_units(target, 2),
+            1..=usize::MAX,
+            &SearchControl::new(),
+        )
+        .unwrap();
+        let sum: i64 = indices.iter().map(|&i| nums[i]).sum();
+        assert_eq!(diff, Amount::from_units(sum - target, 2));
+        assert_eq!(
+            (sum - target).abs(),
+            best,
+            "nums = {:?}, target = {}",
+            nums,
+            target
+        );
+    }
+}
+
+#[test]
+fn meet_in_the_middle_finds_the_same_combinations_as_backtracking() {
+    for (nums, mut rng) in random_cases(31, 200, 3..=13, 30) {
+        let target = Amount::from_units(rng.next(40), 2);
+        let tolerance = Amount::from_units(rng.next(2).abs(), 2);
+
+        let mut expected: Vec<Vec<usize>> = SubsetSumSolutions::new(&amounts(&nums), target)
+            .tolerance(tolerance)
+            .collect();
+        let mut actual = meet_in_the_middle(
+            &amounts(&nums),
+            target,
+            tolerance,
+            1..=usize::MAX,
+            usize::MAX,
+            &SearchControl::new(),
+        )
+        .unwrap();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected, "nums = {:?}, target = {}", nums, target);
+    }
+}
+
+#[test]
+fn meet_in_the_middle_finds_the_closest_combination() {
+    for (nums, mut rng) in random_cases(37, 200, 3..=11, 40) {
+        let len = nums.len();
+        let target = rng.next(200);
+        let min = (rng.next(2).abs() + 1) as usize;
+        let max = min + rng.next(3).unsigned_abs() as usize;
+        let sum = |c: &[usize]| c.iter().map(|&i| nums[i]).sum::<i64>();
+
+        for sizes in [1..=usize::MAX, min..=max] {
+            let best = subsets(len)
+                .filter(|c| sizes.contains(&c.len()))
+                .map(|c| (sum(&c) - target).abs())
+                .min();
+            let closest = closest_in_the_middle(
+                &amounts(&nums),
+                Amount::from_units(target, 2),
+                sizes.clone(),
+                &SearchControl::new(),
+            )
+            .unwrap();
+            assert_eq!(
+                closest.as_ref().map(|(c, _)| (sum(c) - target).abs()),
+                best,
+                "nums = {:?}, target = {}, sizes = {:?}",
+                nums,
+                target,
+                sizes
+            );
+            if let Some((c, diff)) = closest {
+                assert!(sizes.contains(&c.len()));
+                assert_eq!(diff, Amount::from_units(sum(&c) - target, 2));
+            }
+        }
+    }
+}
+
+#[test]
+fn dp_table_agrees_with_brute_force() {
+    for (nums, mut rng) in random_cases(47, 300, 3..=11, 25) {
+        let len = nums.len();
+        let target = rng.next(40);
+        let tolerance = rng.next(2).abs();
+
+        let sums: Vec<i64> = subsets(len)
+            .map(|c| c.iter().map(|&i| nums[i]).sum())
+            .collect();
+        let table = SubsetSumTable::new(&amounts(&nums), 2, &SearchControl::new()).unwrap();
+
+        let found = table.find(
+            Amount::from_units(target, 2),
+            Amount::from_units(tolerance, 2),
+            &SearchControl::new(),
+        );
+        let exists = sums.iter().any(|s| (s - target).abs() <= tolerance);
+        assert_eq!(
+            found.is_some(),
+            exists,
+            "nums = {:?}, target = {}",
+            nums,
+            target
+        );
+        if let Some(indices) = found {
+            let sum: i64 = indices.iter().map(|&i| nums[i]).sum();
+            assert!((sum - target).abs() <= tolerance);
+        }
+
+        let best = sums.iter().map(|s| (s - target).abs()).min().unwrap();
+        let (indices, diff) = table
+            .closest(Amount::from_units(target, 2), &SearchControl::new())
+            .unwrap();
+        let sum: i64 = indices.iter().map(|&i| nums[i]).sum();
+        assert_eq!((sum - target).abs(), best);
+        assert_eq!(diff, Amount::from_units(sum - target, 2));
+    }
+}
+
+#[test]
+fn dp_table_handles_targets_far_outside_its_span() {
+    let nums: Vec<i64> = (1..=30).map(|i| i * 7 - 50).collect();
+    let positive: Vec<usize> = (0..nums.len()).filter(|&i| nums[i] > 0).collect();
+    let negative: Vec<usize> = (0..nums.len()).filter(|&i| nums[i] < 0).collect();
+    let table = SubsetSumTable::new(&amounts(&nums), 2, &SearchControl::new()).unwrap();
+    let far = 10_000_000_000_000;
+
+    // 只扫描表覆盖的和，离得再远也立即给出表的一端
+    let control = SearchControl::new().max_nodes(1 << 20);
+    let (indices, _) = table.closest(Amount::from_units(far, 2), &control).unwrap();
+    assert_eq!(indices, positive);
+    let (indices, _) = table
+        .closest(Amount::from_units(-far, 2), &control)
+        .unwrap();
+    assert_eq!(indices, negative);
+    let half = Amount::from_units(far / 2, 2);
+    assert!(table
+        .find(Amount::from_units(far, 2), half, &control)
+        .is_none());
+    assert_eq!(control.stop_reason(), None);
+}
+
+#[test]
+fn parallel_search_matches_sequential_order() {
+    for (nums, mut rng) in random_cases(59, 50, 8..=16, 30) {
+        let target = Amount::from_units(rng.next(40), 2);
+
+        let expected: Vec<Vec<usize>> = SubsetSumSolutions::new(&amounts(&nums), target).collect();
+        let solutions = SubsetSumSolutions::new(&amounts(&nums), target)
+            .control(Arc::new(SearchControl::new()));
+        for threads in [2, 3] {
+            let all = parallel_solutions(solutions.clone(), usize::MAX, threads, false).unwrap();
+            assert_eq!(all, expected, "nums = {:?}", nums);
+
+            let limited = parallel_solutions(solutions.clone(), 4, threads, false).unwrap();
+            assert_eq!(limited, expected[..expected.len().min(4)]);
+
+            let first = parallel_solutions(solutions.clone(), 1, threads, false).unwrap();
+            assert_eq!(first.len(), expected.len().min(1));
+            assert!(first.iter().all(|c| expected.contains(c)));
+            // 要求顺序时给出的是回溯顺序中的第一个组合
+            let ordered = parallel_solutions(solutions.clone(), 1, threads, true).unwrap();
+            assert_eq!(ordered, expected[..expected.len().min(1)]);
+        }
+    }
+}
+
+#[test]
+fn iterative_deepening_yields_the_fewest_items_first() {
+    for (nums, mut rng) in random_cases(61, 100, 7..=13, 30) {
+        let len = nums.len();
+        let target = rng.next(40);
+        let sum = |c: &[usize]| c.iter().map(|&i| nums[i]).sum::<i64>();
+
+        // 按元素个数排序，个数相同时保持回溯的字典序
+        let mut expected: Vec<Vec<usize>> = subsets(len).filter(|c| sum(c) == target).collect();
+        expected.sort();
+        expected.sort_by_key(|c| c.len());
+
+        let solutions = SubsetSumSolutions::new(&amounts(&nums), Amount::from_units(target, 2));
+        for threads in [1, 3] {
+            let first = fewest_first(solutions.clone(), 1, threads).unwrap();
+            assert_eq!(
+                first,
+                expected[..expected.len().min(1)],
+                "nums = {:?}",
+                nums
+            );
+
+            let limited = fewest_first(solutions.clone(), 5, threads).unwrap();
+            assert_eq!(limited, expected[..expected.len().min(5)]);
+            assert!(limited.windows(2).all(|w| w[0].len() <= w[1].len()));
+        }
+        let sized = fewest_first(solutions.clone().items(3..=4), usize::MAX, 1).unwrap();
+        let in_range: Vec<Vec<usize>> = expected
+            .iter()
+            .filter(|c| (3..=4).contains(&c.len()))
+            .cloned()
+            .collect();
+        assert_eq!(sized, in_range);
+    }
+}
+
+#[test]
+fn item_count_limits_filter_and_prune() {
+    for (nums, mut rng) in random_cases(67, 300, 4..=12, 30) {
+        let len = nums.len();
+        let target = rng.next(40);
+        let min = (rng.next(2).abs() + 1) as usize;
+        let max = min + rng.next(2).unsigned_abs() as usize;
+
+        let subsets: Vec<Vec<usize>> = subsets(len)
+            .filter(|c| (min..=max).contains(&c.len()))
+            .collect();
+        let sum = |c: &[usize]| c.iter().map(|&i| nums[i]).sum::<i64>();
+
+        let mut expected: Vec<Vec<usize>> = subsets
+            .iter()
+            .filter(|c| sum(c) == target)
+            .cloned()
+            .collect();
+        expected.sort();
+        let actual: Vec<Vec<usize>> =
+            SubsetSumSolutions::new(&amounts(&nums), Amount::from_units(target, 2))
+                .items(min..=max)
+                .collect();
+        assert_eq!(actual, expected, "nums = {:?}, {}..={}", nums, min, max);
+
+        let best = subsets.iter().map(|c| (sum(c) - target).abs()).min();
+        let closest = find_closest_combination(
+            &amounts(&nums),
+            Amount::from_units(target, 2),
+            min..=max,
+            &SearchControl::new(),
+        );
+        assert_eq!(closest.as_ref().map(|(c, _)| (sum(c) - target).abs()), best);
+        assert!(closest.is_none_or(|(c, _)| (min..=max).contains(&c.len())));
+    }
+}
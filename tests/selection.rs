@@ -0,0 +1,135 @@
+use find::amount::Amount;
+use find::record::Record;
+use find::selection::RowSelection;
+use std::fs;
+use std::process::Command;
+
+/// 第一列为 ID、第二列为标记的记录，行号从 2 开始（第 1 行是表头）
+fn records(rows: &[(&str, i64, &str)]) -> Vec<Record> {
+    rows.iter()
+        .enumerate()
+        .map(|(i, &(id, units, flag))| Record {
+            line: i + 2,
+            amount: Amount::from_units(units, 2),
+            text: format!("{} {} {}", id, units, flag),
+            fields: vec![id.to_string(), flag.to_string()],
+        })
+        .collect()
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn rows_are_matched_by_id_before_line_number() {
+    let records = records(&[
+        ("3", 100, ""),
+        ("x", 200, ""),
+        ("y", 300, ""),
+        ("z", 400, ""),
+        ("z", 500, ""),
+    ]);
+
+    // `3` 是第一行的 ID，不是第 3 行
+    let selection = RowSelection::new(&records, &strings(&["3"]), &[], None).unwrap();
+    assert_eq!(selection.pinned, [0]);
+    // 没有这个 ID 时按行号匹配；同一 ID 的行全部选中
+    let selection = RowSelection::new(&records, &strings(&["4"]), &strings(&["z"]), None);
+    assert_eq!(
+        selection.unwrap(),
+        RowSelection {
+            pinned: vec![2],
+            excluded: vec![3, 4],
+        }
+    );
+    let missing = RowSelection::new(&records, &strings(&["w"]), &[], None);
+    assert_eq!(missing.unwrap_err(), "--include-rows: no row matches `w`");
+}
+
+#[test]
+fn a_row_cannot_be_both_included_and_excluded() {
+    let records = records(&[("a", 100, ""), ("b", 200, "")]);
+    let conflict = RowSelection::new(&records, &strings(&["a"]), &strings(&["2"]), None);
+    assert_eq!(
+        conflict.unwrap_err(),
+        "line 2 is both included and excluded"
+    );
+    // 重复给出同一行不算冲突
+    let repeated = RowSelection::new(&records, &strings(&["a", "2"]), &[], None).unwrap();
+    assert_eq!(repeated.pinned, [0]);
+}
+
+#[test]
+fn command_line_overrides_the_flag_column() {
+    let records = records(&[
+        ("a", 100, "exclude"),
+        ("b", 200, "+"),
+        ("c", 300, "Include"),
+        ("d", 400, "-"),
+        ("e", 500, "maybe"),
+    ]);
+    let selection = RowSelection::new(&records, &[], &[], Some(1)).unwrap();
+    assert_eq!(selection.pinned, [1, 2]);
+    assert_eq!(selection.excluded, [0, 3]);
+
+    let selection =
+        RowSelection::new(&records, &strings(&["a"]), &strings(&["b"]), Some(1)).unwrap();
+    assert_eq!(selection.pinned, [0, 2]);
+    assert_eq!(selection.excluded, [1, 3]);
+    assert_eq!(selection.candidates(&records), [4]);
+    assert!(RowSelection::new(&records, &[], &[], None)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn pinned_rows_alone_can_meet_the_target() {
+    let dir = std::env::temp_dir().join(format!("find-selection-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.txt");
+    fs::write(&input, "id amount\nA 1.00\nB 2.00\nC 4.00\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_find"))
+        .arg(&input)
+        .args(["--column", "amount", "-t", "3", "--include-rows", "A,B"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    // 固定的两行本身已经凑够目标，不必再加入其他行
+    assert!(
+        stdout.contains("sums up to 3.00: [1.00 (line 2), 2.00 (line 3)]"),
+        "{}",
+        stdout
+    );
+    let result = fs::read_to_string(dir.join("result.csv")).unwrap();
+    assert!(result.starts_with("line,amount,row\n2,1.00,A 1.00\n3,2.00,B 2.00\n"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_minus_flag_in_whitespace_input_is_not_a_trailing_sign() {
+    let dir = std::env::temp_dir().join(format!("find-flag-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.txt");
+    fs::write(&input, "amount flag\n10 +\n20 -\n5 x\n15 x\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_find"))
+        .arg(&input)
+        .args(["-t", "30", "--flag-column", "flag"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    // 标记为 `-` 的 20 被排除而不是当作 -20 拒绝，只能由固定的 10 加上 5 和 15 凑成
+    assert!(
+        stdout.contains("sums up to 30.00: [10.00 (line 2), 5.00 (line 4), 15.00 (line 5)]"),
+        "{}",
+        stdout
+    );
+    assert!(!dir.join("rejects.csv").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// 每个测试单独的临时目录，结果文件写在输入文件旁边
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("find-targets-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_find"))
+        .args(args)
+        .arg("--quiet")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn targets_file_skips_comments_and_a_header() {
+    let dir = temp_dir("file");
+    let input = dir.join("input.txt");
+    fs::write(&input, "1.00\n2.00\n4.00\n").unwrap();
+    let targets = dir.join("targets.txt");
+    fs::write(
+        &targets,
+        "# month end\ntarget\n3\n\n# second batch\n6 rent\n",
+    )
+    .unwrap();
+
+    let output = run(&[
+        input.to_str().unwrap(),
+        "--targets-file",
+        targets.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("sums up to 3.00: [1.00 (line 1), 2.00 (line 2)]"));
+    assert!(stdout.contains("sums up to 6.00: [2.00 (line 2), 4.00 (line 3)]"));
+
+    // 表头只能出现在第一行数据之前
+    fs::write(&targets, "# month end\n3\ntarget\n").unwrap();
+    let output = run(&[
+        input.to_str().unwrap(),
+        "--targets-file",
+        targets.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("line 3: invalid target `target`"),
+        "{}",
+        stderr
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn each_target_gets_its_own_columns_with_sum_and_diff() {
+    let dir = temp_dir("output");
+    let input = dir.join("input.txt");
+    fs::write(&input, "1.00\n2.00\n4.00\n").unwrap();
+
+    let output = run(&[
+        input.to_str().unwrap(),
+        "-t",
+        "3",
+        "-t",
+        "8",
+        "-t",
+        "-5",
+        "--closest",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    // 最接近的组合不能看起来像满足了目标
+    let result = fs::read_to_string(dir.join("result.csv")).unwrap();
+    assert_eq!(
+        result,
+        "line,3.00,row,line,8.00 (closest),row,line,-5.00 (closest),row\n\
+         1,1.00,1.00,1,1.00,1.00,1,1.00,1.00\n\
+         2,2.00,2.00,2,2.00,2.00,,,\n\
+         ,,,3,4.00,4.00,,,\n\
+         sum,3.00,,sum,7.00,,sum,1.00,\n\
+         diff,0.00,,diff,-1.00,,diff,6.00,\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_closest_combination_is_labelled_unlike_a_tolerance_match() {
+    let dir = temp_dir("closest");
+    let input = dir.join("input.txt");
+    fs::write(&input, "10\n20\n40\n").unwrap();
+    let result = |extra: &[&str]| {
+        let args = [&[input.to_str().unwrap(), "-t", "31"], extra].concat();
+        let output = run(&args);
+        assert!(output.status.success());
+        fs::read_to_string(dir.join("result.csv")).unwrap()
+    };
+
+    let body = "1,10.00,10\n2,20.00,20\nsum,30.00,\ndiff,-1.00,\n";
+    assert_eq!(
+        result(&["--tolerance", "1"]),
+        format!("line,amount,row\n{}", body)
+    );
+    assert_eq!(
+        result(&["--closest"]),
+        format!("line,amount (closest),row\n{}", body)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}